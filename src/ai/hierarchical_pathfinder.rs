@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+
+use pathfinding::prelude::{bfs, dijkstra};
+
+use crate::arena::Tilepath;
+
+type Tile = (u32, u32);
+
+/// Edge length in tiles of the square clusters the tile grid is divided into.
+pub const DEFAULT_CLUSTER_SIZE: u32 = 10;
+
+/// Openings between two clusters that are at least this wide get an entrance at each end instead
+/// of a single one in the middle.
+const WIDE_OPENING: u32 = 6;
+
+/// Border between two horizontally or vertically adjacent clusters.
+/// The coordinates identify the cluster to the left of (East) or below (North) the border.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Border {
+    East(u32, u32),
+    North(u32, u32),
+}
+
+/// Tile bounds of a cluster, min inclusive and max exclusive.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min_col: u32,
+    min_row: u32,
+    max_col: u32,
+    max_row: u32,
+}
+
+impl Bounds {
+    fn contains(&self, (col, row): Tile) -> bool {
+        col >= self.min_col && col < self.max_col && row >= self.min_row && row < self.max_row
+    }
+
+    fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min_col: self.min_col.min(other.min_col),
+            min_row: self.min_row.min(other.min_row),
+            max_col: self.max_col.max(other.max_col),
+            max_row: self.max_row.max(other.max_row),
+        }
+    }
+}
+
+/// Precomputed path between two entrances of the same cluster.
+struct Edge {
+    to: Tile,
+    path: Vec<Tile>,
+}
+
+#[derive(Default)]
+struct Cluster {
+    entrances: Vec<Tile>,
+    edges: HashMap<Tile, Vec<Edge>>,
+}
+
+/// Abstraction layer on top of a [Tilepath] which speeds up path queries on large arenas
+/// (HPA*).
+///
+/// The grid is divided into square clusters. Tiles at which a path can cross from one cluster
+/// into its neighbour are entrances, and paths between the entrances of each cluster are
+/// precomputed. Queries then search the much smaller graph of entrances and stitch the cached
+/// paths together.
+///
+/// Only orthogonal moves are supported, i.e. the results match
+/// `find_path(valid_tiles, false, start, end)` in shape, but aren't guaranteed to be the
+/// shortest path.
+pub struct HierarchicalTilepath {
+    cluster_size: u32,
+    ncols: u32,
+    nrows: u32,
+    nclusters_x: u32,
+    nclusters_y: u32,
+    clusters: Vec<Cluster>,
+    /// Pairs of adjacent tiles, one on each side of a border, through which it can be crossed.
+    transitions: HashMap<Border, Vec<(Tile, Tile)>>,
+    /// Entrances reachable from each entrance by crossing into the neighbouring cluster.
+    crossings: HashMap<Tile, Vec<Tile>>,
+}
+
+impl HierarchicalTilepath {
+    pub fn from_tilepath(tilepath: &Tilepath, cluster_size: u32) -> Self {
+        assert!(cluster_size > 0, "cluster size needs to be positive");
+
+        let ncols = tilepath.valid_tiles.len() as u32;
+        let nrows = tilepath.valid_tiles.first().map_or(0, |col| col.len()) as u32;
+        let nclusters_x = (ncols + cluster_size - 1) / cluster_size;
+        let nclusters_y = (nrows + cluster_size - 1) / cluster_size;
+
+        let mut hierarchy = Self {
+            cluster_size,
+            ncols,
+            nrows,
+            nclusters_x,
+            nclusters_y,
+            clusters: (0..nclusters_x * nclusters_y)
+                .map(|_| Cluster::default())
+                .collect(),
+            transitions: HashMap::new(),
+            crossings: HashMap::new(),
+        };
+
+        for cy in 0..nclusters_y {
+            for cx in 0..nclusters_x {
+                for border in hierarchy.outgoing_borders((cx, cy)) {
+                    hierarchy.detect_transitions(tilepath, border);
+                }
+            }
+        }
+        hierarchy.rebuild_crossings();
+        for cy in 0..nclusters_y {
+            for cx in 0..nclusters_x {
+                hierarchy.rebuild_cluster(tilepath, (cx, cy));
+            }
+        }
+        hierarchy
+    }
+
+    /// Updates the abstraction after the validity of the tile at `(col, row)` changed in the
+    /// `tilepath`. Only the cluster containing the tile and its direct neighbours are rebuilt.
+    pub fn update_tile(&mut self, tilepath: &Tilepath, tile: (u32, u32)) {
+        let (cx, cy) = self.cluster_of(tile);
+        let borders = self.borders((cx, cy));
+        for border in &borders {
+            self.detect_transitions(tilepath, *border);
+        }
+        self.rebuild_crossings();
+
+        self.rebuild_cluster(tilepath, (cx, cy));
+        for border in borders {
+            let neighbour = match border {
+                Border::East(x, y) if x == cx => (x + 1, y),
+                Border::North(x, y) if y == cy => (x, y + 1),
+                Border::East(x, y) | Border::North(x, y) => (x, y),
+            };
+            self.rebuild_cluster(tilepath, neighbour);
+        }
+    }
+
+    /// Finds a path from `start` to `end` which excludes the `start` tile and includes the `end`
+    /// tile, the same shape as returned by [super::find_path].
+    pub fn find_path(
+        &self,
+        tilepath: &Tilepath,
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> Option<Vec<(u32, u32)>> {
+        let outside = |(col, row): Tile| col >= self.ncols || row >= self.nrows;
+        if start == end || outside(start) || outside(end) {
+            return None;
+        }
+        let start_cluster = self.cluster_of(start);
+        let end_cluster = self.cluster_of(end);
+        // Nearby tiles are resolved directly since the detour via entrances would be significant.
+        let adjacent = (start_cluster.0 as i64 - end_cluster.0 as i64).abs() <= 1
+            && (start_cluster.1 as i64 - end_cluster.1 as i64).abs() <= 1;
+        if adjacent {
+            let bounds = self.bounds(start_cluster).union(&self.bounds(end_cluster));
+            let local = local_path(tilepath, bounds, start, end);
+            if local.is_some() {
+                return local;
+            }
+        }
+
+        let start_paths: HashMap<Tile, Vec<Tile>> = self
+            .cluster(start_cluster)
+            .entrances
+            .iter()
+            .filter_map(|&entrance| {
+                local_path(tilepath, self.bounds(start_cluster), start, entrance)
+                    .map(|path| (entrance, path))
+            })
+            .collect();
+        let end_paths: HashMap<Tile, Vec<Tile>> = self
+            .cluster(end_cluster)
+            .entrances
+            .iter()
+            .filter_map(|&entrance| {
+                local_path(tilepath, self.bounds(end_cluster), entrance, end)
+                    .map(|path| (entrance, path))
+            })
+            .collect();
+
+        let (nodes, _) = dijkstra(
+            &start,
+            |&node| {
+                let mut xs: Vec<(Tile, usize)> = Vec::new();
+                if node == start {
+                    xs.extend(start_paths.iter().map(|(&to, path)| (to, path.len())));
+                }
+                if let Some(path) = end_paths.get(&node) {
+                    xs.push((end, path.len()));
+                }
+                if let Some(edges) = self.cluster(self.cluster_of(node)).edges.get(&node) {
+                    xs.extend(edges.iter().map(|edge| (edge.to, edge.path.len())));
+                }
+                if let Some(others) = self.crossings.get(&node) {
+                    xs.extend(others.iter().map(|&other| (other, 1)));
+                }
+                xs
+            },
+            |&node| node == end,
+        )?;
+
+        let mut path: Vec<Tile> = Vec::new();
+        for pair in nodes.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if from == start && start_paths.contains_key(&to) {
+                path.extend(&start_paths[&to]);
+            } else if to == end && end_paths.contains_key(&from) {
+                path.extend(&end_paths[&from]);
+            } else if let Some(edge) = self.edge(from, to) {
+                path.extend(&edge.path);
+            } else {
+                // crossing into the neighbouring cluster
+                path.push(to);
+            }
+        }
+        Some(path).filter(|tiles| !tiles.is_empty())
+    }
+
+    fn cluster_of(&self, (col, row): Tile) -> (u32, u32) {
+        (col / self.cluster_size, row / self.cluster_size)
+    }
+
+    fn cluster(&self, (cx, cy): (u32, u32)) -> &Cluster {
+        &self.clusters[(cy * self.nclusters_x + cx) as usize]
+    }
+
+    fn cluster_mut(&mut self, (cx, cy): (u32, u32)) -> &mut Cluster {
+        &mut self.clusters[(cy * self.nclusters_x + cx) as usize]
+    }
+
+    fn edge(&self, from: Tile, to: Tile) -> Option<&Edge> {
+        self.cluster(self.cluster_of(from))
+            .edges
+            .get(&from)?
+            .iter()
+            .find(|edge| edge.to == to)
+    }
+
+    fn bounds(&self, (cx, cy): (u32, u32)) -> Bounds {
+        Bounds {
+            min_col: cx * self.cluster_size,
+            min_row: cy * self.cluster_size,
+            max_col: ((cx + 1) * self.cluster_size).min(self.ncols),
+            max_row: ((cy + 1) * self.cluster_size).min(self.nrows),
+        }
+    }
+
+    /// Borders on the right and top side of the cluster.
+    fn outgoing_borders(&self, (cx, cy): (u32, u32)) -> Vec<Border> {
+        let mut borders = Vec::new();
+        if cx + 1 < self.nclusters_x {
+            borders.push(Border::East(cx, cy));
+        }
+        if cy + 1 < self.nclusters_y {
+            borders.push(Border::North(cx, cy));
+        }
+        borders
+    }
+
+    /// All borders the cluster shares with its neighbours.
+    fn borders(&self, (cx, cy): (u32, u32)) -> Vec<Border> {
+        let mut borders = self.outgoing_borders((cx, cy));
+        if cx > 0 {
+            borders.push(Border::East(cx - 1, cy));
+        }
+        if cy > 0 {
+            borders.push(Border::North(cx, cy - 1));
+        }
+        borders
+    }
+
+    fn detect_transitions(&mut self, tilepath: &Tilepath, border: Border) {
+        let pairs: Vec<(Tile, Tile)> = match border {
+            Border::East(cx, cy) => {
+                let bounds = self.bounds((cx, cy));
+                let col = bounds.max_col - 1;
+                (bounds.min_row..bounds.max_row)
+                    .map(|row| ((col, row), (col + 1, row)))
+                    .collect()
+            }
+            Border::North(cx, cy) => {
+                let bounds = self.bounds((cx, cy));
+                let row = bounds.max_row - 1;
+                (bounds.min_col..bounds.max_col)
+                    .map(|col| ((col, row), (col, row + 1)))
+                    .collect()
+            }
+        };
+
+        let mut transitions = Vec::new();
+        let mut opening: Vec<(Tile, Tile)> = Vec::new();
+        for (inside, outside) in pairs {
+            let open =
+                tilepath.is_valid(inside.0, inside.1) && tilepath.is_valid(outside.0, outside.1);
+            if open {
+                opening.push((inside, outside));
+            } else {
+                add_entrances(&mut transitions, &opening);
+                opening.clear();
+            }
+        }
+        add_entrances(&mut transitions, &opening);
+
+        self.transitions.insert(border, transitions);
+    }
+
+    fn rebuild_crossings(&mut self) {
+        self.crossings.clear();
+        for &(a, b) in self.transitions.values().flatten() {
+            self.crossings.entry(a).or_insert_with(Vec::new).push(b);
+            self.crossings.entry(b).or_insert_with(Vec::new).push(a);
+        }
+    }
+
+    fn rebuild_cluster(&mut self, tilepath: &Tilepath, cluster: (u32, u32)) {
+        let bounds = self.bounds(cluster);
+        let mut entrances: Vec<Tile> = self
+            .crossings
+            .keys()
+            .filter(|&&tile| bounds.contains(tile))
+            .cloned()
+            .collect();
+        entrances.sort();
+
+        let mut edges: HashMap<Tile, Vec<Edge>> = HashMap::new();
+        for &from in &entrances {
+            let from_edges = entrances
+                .iter()
+                .filter(|&&to| to != from)
+                .filter_map(|&to| {
+                    local_path(tilepath, bounds, from, to).map(|path| Edge { to, path })
+                })
+                .collect();
+            edges.insert(from, from_edges);
+        }
+
+        let cluster = self.cluster_mut(cluster);
+        cluster.entrances = entrances;
+        cluster.edges = edges;
+    }
+}
+
+fn add_entrances(transitions: &mut Vec<(Tile, Tile)>, opening: &[(Tile, Tile)]) {
+    let width = opening.len() as u32;
+    if width == 0 {
+        return;
+    }
+    if width < WIDE_OPENING {
+        transitions.push(opening[(opening.len() - 1) / 2]);
+    } else {
+        transitions.push(opening[0]);
+        transitions.push(opening[opening.len() - 1]);
+    }
+}
+
+/// Orthogonal path which never leaves the given bounds.
+fn local_path(tilepath: &Tilepath, bounds: Bounds, start: Tile, end: Tile) -> Option<Vec<Tile>> {
+    let result = bfs(
+        &start,
+        |&(col, row)| {
+            let mut xs: Vec<Tile> = Vec::with_capacity(4);
+            if col > 0 {
+                xs.push((col - 1, row));
+            }
+            if row > 0 {
+                xs.push((col, row - 1));
+            }
+            xs.push((col + 1, row));
+            xs.push((col, row + 1));
+            xs.into_iter()
+                .filter(|&tile| bounds.contains(tile) && tilepath.is_valid(tile.0, tile.1))
+                .collect::<Vec<_>>()
+        },
+        |&tile| tile == end,
+    );
+    result
+        .map(|tiles| tiles.into_iter().skip(1).collect())
+        .filter(|tiles: &Vec<_>| !tiles.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::{
+        ai::find_path,
+        arena::{levels::Levels, Arena},
+    };
+
+    fn init_tile_path(level: &'static str) -> Tilepath {
+        let tile_size = 1;
+        let arena = Arena::for_level(level, tile_size).expect("FATAL: unable to create arena");
+        Tilepath::from_arena(&arena)
+    }
+
+    fn assert_valid_path(tilepath: &Tilepath, start: Tile, end: Tile, path: &[Tile]) {
+        let mut prev = start;
+        for &tile in path {
+            let (dc, dr) = (
+                (tile.0 as i64 - prev.0 as i64).abs(),
+                (tile.1 as i64 - prev.1 as i64).abs(),
+            );
+            assert_eq!(dc + dr, 1, "{:?} -> {:?} is an orthogonal step", prev, tile);
+            assert!(tilepath.is_valid(tile.0, tile.1), "{:?} is valid", tile);
+            prev = tile;
+        }
+        assert_eq!(prev, end, "path ends at {:?}", end);
+    }
+
+    /// Pairs of floor tiles spread across the entire arena.
+    fn sample_routes(tilepath: &Tilepath, count: usize) -> Vec<(Tile, Tile)> {
+        let mut tiles: Vec<Tile> = Vec::new();
+        for (col, rows) in tilepath.valid_tiles.iter().enumerate() {
+            for (row, valid) in rows.iter().enumerate() {
+                if *valid {
+                    tiles.push((col as u32, row as u32));
+                }
+            }
+        }
+        let step = (tiles.len() / count).max(1);
+        (0..tiles.len())
+            .step_by(step)
+            .map(|idx| (tiles[idx], tiles[tiles.len() - 1 - idx]))
+            .filter(|(start, end)| start != end)
+            .collect()
+    }
+
+    #[test]
+    fn find_path_neighbouring_clusters() {
+        let tilepath = init_tile_path("face off");
+        let hierarchy = HierarchicalTilepath::from_tilepath(&tilepath, DEFAULT_CLUSTER_SIZE);
+        let path = hierarchy.find_path(&tilepath, (11, 19), (11, 21));
+        let expected: Vec<(u32, u32)> = vec![(11, 20), (11, 21)];
+        assert_eq!(path, Some(expected));
+    }
+
+    #[test]
+    fn find_path_across_arena() {
+        let tilepath = init_tile_path("face off");
+        let hierarchy = HierarchicalTilepath::from_tilepath(&tilepath, DEFAULT_CLUSTER_SIZE);
+        let path = hierarchy
+            .find_path(&tilepath, (5, 25), (62, 25))
+            .expect("should find path");
+        assert_valid_path(&tilepath, (5, 25), (62, 25), &path);
+        assert!(path.len() >= 71, "never shorter than the optimal path");
+    }
+
+    #[test]
+    fn finds_same_routes_as_bfs_on_all_levels() {
+        let levels = Levels::new();
+        for level in levels.names() {
+            let tilepath = init_tile_path(level);
+            let hierarchy = HierarchicalTilepath::from_tilepath(&tilepath, DEFAULT_CLUSTER_SIZE);
+            for (start, end) in sample_routes(&tilepath, 40) {
+                let expected = find_path(&tilepath.valid_tiles, false, start, end);
+                let path = hierarchy.find_path(&tilepath, start, end);
+                assert_eq!(
+                    path.is_some(),
+                    expected.is_some(),
+                    "{}: {:?} -> {:?}",
+                    level,
+                    start,
+                    end
+                );
+                if let Some(path) = path {
+                    assert_valid_path(&tilepath, start, end, &path);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn update_tile_blocking_and_reopening_corridor() {
+        // corridor of width 1 spanning two clusters
+        let (ncols, nrows) = (8, 3);
+        let mut invalids: Vec<(usize, usize)> = Vec::new();
+        for col in 0..ncols {
+            invalids.push((col, 0));
+            invalids.push((col, 2));
+        }
+        let mut tilepath = Tilepath::with_invalids(ncols, nrows, invalids);
+        let mut hierarchy = HierarchicalTilepath::from_tilepath(&tilepath, 4);
+
+        let path = hierarchy.find_path(&tilepath, (0, 1), (7, 1));
+        assert_eq!(path.map(|x| x.len()), Some(7));
+
+        tilepath.valid_tiles[4][1] = false;
+        hierarchy.update_tile(&tilepath, (4, 1));
+        assert_eq!(hierarchy.find_path(&tilepath, (0, 1), (7, 1)), None);
+
+        tilepath.valid_tiles[4][1] = true;
+        hierarchy.update_tile(&tilepath, (4, 1));
+        let path = hierarchy.find_path(&tilepath, (0, 1), (7, 1));
+        assert_eq!(path.map(|x| x.len()), Some(7));
+    }
+
+    // Run via: cargo test --release bench_builtin_levels -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_builtin_levels() {
+        let levels = Levels::new();
+        for level in levels.names() {
+            let tilepath = init_tile_path(level);
+
+            let build_start = Instant::now();
+            let hierarchy = HierarchicalTilepath::from_tilepath(&tilepath, DEFAULT_CLUSTER_SIZE);
+            let build = build_start.elapsed();
+
+            let routes = sample_routes(&tilepath, 200);
+            let (mut bfs_total, mut hpa_total) = (Duration::default(), Duration::default());
+            let (mut bfs_len, mut hpa_len) = (0, 0);
+            for &(start, end) in &routes {
+                let bfs_start = Instant::now();
+                let expected = find_path(&tilepath.valid_tiles, false, start, end);
+                bfs_total += bfs_start.elapsed();
+
+                let hpa_start = Instant::now();
+                let path = hierarchy.find_path(&tilepath, start, end);
+                hpa_total += hpa_start.elapsed();
+
+                bfs_len += expected.map_or(0, |x| x.len());
+                hpa_len += path.map_or(0, |x| x.len());
+            }
+            println!(
+                "{:>16}: {} routes | bfs {:>10?} | hpa {:>10?} (build {:?}) | path length hpa/bfs {:.3}",
+                level,
+                routes.len(),
+                bfs_total,
+                hpa_total,
+                build,
+                hpa_len as f32 / bfs_len.max(1) as f32
+            );
+        }
+    }
+}
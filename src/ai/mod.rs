@@ -1,4 +1,6 @@
+mod hierarchical_pathfinder;
 mod pathfinder;
 mod shooter;
-pub(crate) use pathfinder::*;
-pub(crate) use shooter::*;
+pub use hierarchical_pathfinder::*;
+pub use pathfinder::*;
+pub use shooter::*;
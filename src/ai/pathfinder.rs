@@ -16,6 +16,42 @@ pub fn find_path(
         .filter(|tiles: &Vec<_>| !tiles.is_empty())
 }
 
+/// Neighbouring tile which increases the distance to the threat the most, if any.
+pub fn retreat_from(
+    valid_tiles: &[Vec<bool>],
+    tile: (u32, u32),
+    threat: (u32, u32),
+) -> Option<(u32, u32)> {
+    let distance_to_threat = |(col, row): (u32, u32)| {
+        let dc = col as i64 - threat.0 as i64;
+        let dr = row as i64 - threat.1 as i64;
+        dc * dc + dr * dr
+    };
+    // tiles past the last column or row are outside the grid
+    let is_valid = |(col, row): (u32, u32)| {
+        valid_tiles
+            .get(col as usize)
+            .and_then(|rows| rows.get(row as usize))
+            == Some(&true)
+    };
+
+    let (col, row) = tile;
+    let mut neighbours = vec![(col + 1, row), (col, row + 1)];
+    if col > 0 {
+        neighbours.push((col - 1, row));
+    }
+    if row > 0 {
+        neighbours.push((col, row - 1));
+    }
+
+    let current_distance = distance_to_threat(tile);
+    neighbours
+        .into_iter()
+        .filter(|&tile| is_valid(tile))
+        .filter(|&tile| distance_to_threat(tile) > current_distance)
+        .max_by_key(|&tile| distance_to_threat(tile))
+}
+
 fn moves(valid_tiles: &Vec<Vec<bool>>, allow_diagonals: bool, tile: (u32, u32)) -> Vec<(u32, u32)> {
     let (col, row) = tile;
     let mut xs: Vec<(u32, u32)> = Vec::new();
@@ -82,4 +118,11 @@ mod test {
         let path = find_path(&tilepath.valid_tiles, false, (5, 25), (62, 25));
         assert_eq!(path.map(|x| x.len()), Some(71));
     }
+
+    #[test]
+    fn retreat_from_grid_edge() {
+        let valid_tiles = vec![vec![true; 3]; 3];
+        assert_eq!(retreat_from(&valid_tiles, (2, 2), (1, 2)), Some((2, 1)));
+        assert_eq!(retreat_from(&valid_tiles, (2, 2), (2, 1)), Some((1, 2)));
+    }
 }
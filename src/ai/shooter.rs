@@ -1,8 +1,16 @@
-use std::fmt::Display;
+use std::{f32::consts::PI, fmt::Display};
 
 use crisscross::{AngleRad, Grid, TileRaycaster};
 
-use crate::{arena::Tilepath, engine::TilePosition};
+use crate::{
+    arena::Tilepath,
+    engine::{physics::reflect_off_walls, TilePosition},
+};
+
+/// Angle between the directions that are tried when looking for a bank shot
+const BANK_SHOT_ANGLE_STEP_DEG: usize = 1;
+/// How far to step back from a wall before reflecting off of it, in tiles
+const BANK_SHOT_WALL_CLEARANCE: f32 = 0.01;
 
 pub fn create_tile_caster(ncols: u32, nrows: u32, tile_size: f32) -> TileRaycaster {
     let grid = Grid::new(ncols, nrows, tile_size as f32);
@@ -13,6 +21,18 @@ fn convert_position(tp: &TilePosition) -> crisscross::TilePosition {
     crisscross::TilePosition::new(tp.col, tp.row, tp.rel_x, tp.rel_y)
 }
 
+/// Angle in radians from origin to target in the range `0..2PI`, counter clockwise starting
+/// at the positive x-axis.
+fn angle_between(origin: &TilePosition, target: &TilePosition) -> f32 {
+    let (dx, dy) = origin.delta_to(target);
+    let angle = dy.atan2(dx);
+    if angle < 0.0 {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
 /// A potential shot
 #[derive(Debug)]
 pub struct Shot {
@@ -33,6 +53,113 @@ impl Display for Shot {
     }
 }
 
+/// Where a ray cast through the tilemap stopped
+#[derive(Debug, PartialEq)]
+pub struct RayHit {
+    /// Relative distance from origin to where the ray stopped
+    pub distance: f32,
+    /// Index of the target that was hit if the ray didn't stop at a wall
+    pub target_idx: Option<usize>,
+}
+
+/// Casts a ray from origin in the given direction until it hits one of the targets or a wall.
+/// Returns `None` if the ray leaves the tilemap without hitting anything.
+pub fn cast_ray(
+    tc: &TileRaycaster,
+    tile_path: &Tilepath,
+    origin: &TilePosition,
+    direction: f32,
+    targets: &[TilePosition],
+) -> Option<RayHit> {
+    let origin = convert_position(origin);
+    let targets: Vec<crisscross::TilePosition> = targets.iter().map(convert_position).collect();
+    let angle: AngleRad = direction.into();
+    let first_invalid = tc.first_invalid(&origin, angle, |tp| {
+        let hit_target = targets.iter().any(|target| tp.is_same_tile(target));
+        !hit_target && tile_path.is_valid(tp.x, tp.y)
+    })?;
+    let target_idx = targets
+        .iter()
+        .position(|target| first_invalid.is_same_tile(target));
+    Some(RayHit {
+        distance: origin.distance_relative(&first_invalid),
+        target_idx,
+    })
+}
+
+/// Finds the shortest shot at the target which bounces off at most `max_bounces` walls and
+/// travels no further than `range` tiles. Each leg of the shot is traced with the raycaster and
+/// reflected off the wall face it hits.
+pub fn find_bank_shot(
+    tc: &TileRaycaster,
+    tile_path: &Tilepath,
+    origin: &TilePosition,
+    target: &TilePosition,
+    max_bounces: u8,
+    range: f32,
+) -> Option<Shot> {
+    (0..360)
+        .step_by(BANK_SHOT_ANGLE_STEP_DEG)
+        .filter_map(|degrees| {
+            let direction = (degrees as f32).to_radians();
+            let distance =
+                trace_bank_shot(tc, tile_path, origin, target, direction, max_bounces, range)?;
+            Some(Shot {
+                direction,
+                distance,
+            })
+        })
+        .min_by(|a, b| {
+            a.distance
+                .partial_cmp(&b.distance)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+/// Distance the shot travels until it hits the target, if it does.
+fn trace_bank_shot(
+    tc: &TileRaycaster,
+    tile_path: &Tilepath,
+    origin: &TilePosition,
+    target: &TilePosition,
+    direction: f32,
+    max_bounces: u8,
+    range: f32,
+) -> Option<f32> {
+    let targets = [target.clone()];
+    let mut position = origin.clone();
+    let mut direction = direction;
+    let mut traveled = 0.0;
+    for _ in 0..=max_bounces {
+        let hit = cast_ray(tc, tile_path, &position, direction, &targets)?;
+        traveled += hit.distance;
+        if traveled > range {
+            return None;
+        }
+        if hit.target_idx.is_some() {
+            return Some(traveled);
+        }
+
+        // step back from the wall and bounce off of it the same way projectiles do
+        let (x, y) = position.axes();
+        let (dx, dy) = (direction.cos(), direction.sin());
+        let before_wall = (
+            x + dx * (hit.distance - BANK_SHOT_WALL_CLEARANCE),
+            y + dy * (hit.distance - BANK_SHOT_WALL_CLEARANCE),
+        );
+        if !tile_path.is_valid_at(before_wall.0, before_wall.1) {
+            return None;
+        }
+        let step = 2.0 * BANK_SHOT_WALL_CLEARANCE;
+        let (vx, vy) = reflect_off_walls(before_wall, (dx * step, dy * step), |x, y| {
+            !tile_path.is_valid_at(x, y)
+        })?;
+        direction = vy.atan2(vx);
+        position = TilePosition::from(before_wall);
+    }
+    None
+}
+
 #[allow(dead_code)]
 pub fn find_shot(
     tc: &TileRaycaster,
@@ -40,11 +167,10 @@ pub fn find_shot(
     origin: &TilePosition,
     target: &TilePosition,
 ) -> Option<Shot> {
+    let direction = angle_between(origin, target);
     let origin = convert_position(origin);
     let target = convert_position(target);
-    // TODO(thlorenz): TilePosition.angle_to implementation got lost it seems and therefore we
-    // hacked the angle just to be able to build for now
-    let angle: AngleRad = (0.0).into(); // origin.angle_to(&target);
+    let angle: AngleRad = direction.into();
     let first_invalid = tc.first_invalid(&origin, angle.clone(), |tp| {
         if tp.is_same_tile(&target) {
             // hit the target
@@ -58,7 +184,7 @@ pub fn find_shot(
     if first_invalid.is_same_tile(&target) {
         let distance = origin.distance_relative(&target);
         Some(Shot {
-            direction: 0.0,
+            direction,
             distance,
         })
     } else {
@@ -145,4 +271,57 @@ mod tests {
         let target = TilePosition::new(5, 7, 0.9, 0.9);
         assert_shot_eq!(find_shot(&tc, &tile_path, &origin, &target), (9.161, 53.9));
     }
+
+    #[test]
+    fn bank_shot() {
+        // walled in 5x5 room with a wall between origin and target
+        let (ncols, nrows) = (7, 7);
+        let mut invalids = vec![(1, 3), (2, 3), (3, 3)];
+        for idx in 0..7 {
+            invalids.extend(vec![(idx, 0), (idx, 6), (0, idx), (6, idx)]);
+        }
+        let tile_path = Tilepath::with_invalids(ncols, nrows, invalids);
+        let tc = create_tile_caster(ncols as u32, nrows as u32, 1.0);
+
+        let origin = TilePosition::new(2, 1, 0.5, 0.5);
+        let target = TilePosition::new(2, 5, 0.5, 0.5);
+        assert_eq!(find_shot(&tc, &tile_path, &origin, &target), None);
+
+        let shot = find_bank_shot(&tc, &tile_path, &origin, &target, 1, 20.0)
+            .expect("should bank off the right wall")
+            .degrees();
+        assert_eq!(round(shot.distance, 0), 8.0);
+        assert!(shot.direction >= 28.0 && shot.direction <= 32.0);
+
+        assert!(
+            find_bank_shot(&tc, &tile_path, &origin, &target, 0, 20.0).is_none(),
+            "needs to bounce"
+        );
+        assert!(
+            find_bank_shot(&tc, &tile_path, &origin, &target, 1, 5.0).is_none(),
+            "out of range"
+        );
+    }
+
+    #[test]
+    fn ray_hits() {
+        let (ncols, nrows) = (8, 8);
+        let tile_path = Tilepath::with_invalids(ncols as usize, nrows as usize, vec![(4, 0)]);
+        let tc = create_tile_caster(ncols, nrows, 1.0);
+        let origin = TilePosition::new(0, 0, 0.5, 0.5);
+        let targets = vec![
+            TilePosition::new(2, 0, 0.5, 0.5),
+            TilePosition::new(0, 3, 0.5, 0.5),
+        ];
+
+        let hit = cast_ray(&tc, &tile_path, &origin, 0.0, &targets).expect("hits target");
+        assert_eq!(hit.target_idx, Some(0), "first target in line of fire");
+
+        let hit = cast_ray(&tc, &tile_path, &origin, PI / 2.0, &targets).expect("hits target");
+        assert_eq!(hit.target_idx, Some(1));
+
+        let hit = cast_ray(&tc, &tile_path, &origin, 0.0, &targets[1..]).expect("hits wall");
+        assert_eq!(hit.target_idx, None, "stopped by wall");
+        assert!(hit.distance >= 3.5 && hit.distance < 4.5);
+    }
 }
@@ -1,5 +1,5 @@
 mod axis;
 mod rolling_box;
 
-pub(crate) use axis::*;
-pub(crate) use rolling_box::*;
+pub use axis::*;
+pub use rolling_box::*;
@@ -1,16 +1,28 @@
 use crate::arena::levels::Levels;
+use crate::arena::metadata::{EnemySpawn, LevelMetadata, PatrolRoute};
 use crate::arena::tilemap::{needs_floor_tile, Tile, Tilemap};
+use crate::arena::wall_theme::DEFAULT_WALL_THEME;
 use crate::engine::TilePosition;
 use std::error::Error;
 use std::fmt;
+use std::fs;
+use std::path::Path;
 
 #[derive(fmt::Debug)]
 pub struct Arena {
     pub floor_tiles: Vec<TilePosition>,
     pub walls: Vec<TilePosition>,
     pub player: TilePosition,
+    pub bombs: Vec<TilePosition>,
+    pub holes: Vec<TilePosition>,
     pub ncols: u32,
     pub nrows: u32,
+    pub patrol_routes: Vec<PatrolRoute>,
+    pub enemy_spawns: Vec<EnemySpawn>,
+    /// Name of the theme walls are rendered with
+    pub wall_theme: String,
+    /// Name of the level the arena was created for
+    pub level: String,
 }
 
 impl Arena {
@@ -25,35 +37,46 @@ impl Arena {
             floor_tiles,
             walls,
             player,
+            bombs: Vec::new(),
+            holes: Vec::new(),
             ncols,
             nrows,
+            patrol_routes: Vec::new(),
+            enemy_spawns: Vec::new(),
+            wall_theme: DEFAULT_WALL_THEME.to_string(),
+            level: String::new(),
         }
     }
 
-    pub fn from_tilemap(tilemap: Tilemap) -> Arena {
+    pub fn from_tilemap(tilemap: Tilemap) -> Result<Arena, Box<dyn Error>> {
         let nrows = tilemap.nrows;
         let ncols = tilemap.ncols;
         let mut floor_tiles: Vec<TilePosition> = Vec::new();
         let mut walls: Vec<TilePosition> = Vec::new();
         let mut player: Option<TilePosition> = None;
+        let mut bombs: Vec<TilePosition> = Vec::new();
+        let mut holes: Vec<TilePosition> = Vec::new();
         for row in 0..nrows {
             for col in 0..ncols {
                 let idx: usize = (row * ncols + col) as usize;
-                let tile = tilemap.tiles.get(idx).expect("should have tile at idx");
+                let tile = tilemap
+                    .tiles
+                    .get(idx)
+                    .ok_or_else(|| format!("terrain is missing tile at {},{}", col, row))?;
                 if needs_floor_tile(tile) {
                     floor_tiles.push(TilePosition::centered(col, row, tilemap.tile_size))
                 }
                 match tile {
                     Tile::OutOfBounds => {}
                     Tile::Empty => {}
-                    Tile::Hole => {}
+                    Tile::Hole => holes.push(TilePosition::centered(col, row, tilemap.tile_size)),
                     Tile::Wall => walls.push(TilePosition::centered(col, row, tilemap.tile_size)),
                     Tile::Player => {
                         player = Some(TilePosition::centered(col, row, tilemap.tile_size))
                     }
                     Tile::Medkit => {}
                     Tile::Shield => {}
-                    Tile::Bomb => {}
+                    Tile::Bomb => bombs.push(TilePosition::centered(col, row, tilemap.tile_size)),
                     Tile::Teleport1 => {}
                     Tile::Teleport2 => {}
                     Tile::Teleport3 => {}
@@ -65,23 +88,96 @@ impl Arena {
                 }
             }
         }
-        let player = player.expect("Terrain is missing player");
-        Arena::new(floor_tiles, walls, player, ncols, nrows)
+        let player = player.ok_or("terrain is missing a player")?;
+        let mut arena = Arena::new(floor_tiles, walls, player, ncols, nrows);
+        arena.bombs = bombs;
+        arena.holes = holes;
+        Ok(arena)
     }
 
-    pub fn for_level(level_name: &'static str, tile_size: u32) -> Result<Arena, Box<dyn Error>> {
+    /// Creates the arena of a builtin level or of the level file at the given path.
+    pub fn for_level(level_name: &str, tile_size: u32) -> Result<Arena, Box<dyn Error>> {
         let levels = Levels::new();
-        let face_off = levels
-            .get_level(level_name)
-            .ok_or(format!("level not found '{}'", level_name))?;
-        let tilemap = Tilemap::new(face_off.terrain, tile_size)?;
-        Ok(Arena::from_tilemap(tilemap))
+        let terrain = match levels.get_level(level_name) {
+            Some(level) => level.terrain.to_string(),
+            None if Path::new(level_name).is_file() => fs::read_to_string(level_name)
+                .map_err(|err| format!("unable to read level '{}': {}", level_name, err))?,
+            None => return Err(format!("level not found '{}'", level_name).into()),
+        };
+        let mut arena = Arena::from_terrain(&terrain, tile_size)?;
+        arena.level = level_name.to_string();
+        Ok(arena)
+    }
+
+    /// Creates the arena of a terrain, followed by its optional metadata sections.
+    pub fn from_terrain(terrain: &str, tile_size: u32) -> Result<Arena, Box<dyn Error>> {
+        let tilemap = Tilemap::new(terrain, tile_size)?;
+        let metadata = LevelMetadata::parse(terrain)?;
+        let mut arena = Arena::from_tilemap(tilemap)?;
+        arena.add_patrol_routes(metadata.patrol_routes)?;
+        arena.add_enemy_spawns(metadata.enemy_spawns)?;
+        if let Some(wall_theme) = metadata.wall_theme {
+            arena.wall_theme = wall_theme;
+        }
+        Ok(arena)
+    }
+
+    pub fn patrol_route(&self, name: &str) -> Option<&PatrolRoute> {
+        self.patrol_routes.iter().find(|route| route.name == name)
+    }
+
+    pub fn is_floor_tile(&self, (col, row): (u32, u32)) -> bool {
+        self.floor_tiles
+            .iter()
+            .any(|tile| tile.col == col && tile.row == row)
+    }
+
+    fn add_patrol_routes(&mut self, routes: Vec<PatrolRoute>) -> Result<(), Box<dyn Error>> {
+        for route in routes {
+            let invalid = route
+                .waypoints
+                .iter()
+                .find(|&&waypoint| !self.is_floor_tile(waypoint));
+            if let Some((col, row)) = invalid {
+                return Err(format!(
+                    "patrol route '{}' waypoint ({}, {}) is not a floor tile",
+                    route.name, col, row
+                )
+                .into());
+            }
+            self.patrol_routes.push(route);
+        }
+        Ok(())
+    }
+
+    fn add_enemy_spawns(&mut self, spawns: Vec<EnemySpawn>) -> Result<(), Box<dyn Error>> {
+        for spawn in spawns {
+            if !self.is_floor_tile(spawn.tile) {
+                return Err(format!(
+                    "enemy spawn '{}' at ({}, {}) is not on a floor tile",
+                    spawn.archetype, spawn.tile.0, spawn.tile.1
+                )
+                .into());
+            }
+            if let Some(route) = &spawn.patrol_route {
+                if self.patrol_route(route).is_none() {
+                    return Err(format!(
+                        "enemy spawn '{}' references unknown patrol route '{}'",
+                        spawn.archetype, route
+                    )
+                    .into());
+                }
+            }
+            self.enemy_spawns.push(spawn);
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arena::metadata::PatrolMode;
 
     const TILE_SIZE: u32 = 2;
     const CENTER: f32 = TILE_SIZE as f32 / 2.0;
@@ -95,7 +191,7 @@ mod tests {
 
         let tilemap =
             Tilemap::new(small_terrain, TILE_SIZE).expect("should return correct tilemap");
-        let arena = Arena::from_tilemap(tilemap);
+        let arena = Arena::from_tilemap(tilemap).expect("should create arena");
         let floor_tiles = &arena.floor_tiles;
         assert_eq!(floor_tiles.len(), 2, "has two floor tiles");
 
@@ -106,4 +202,107 @@ mod tests {
 
         print!("{:?}", arena)
     }
+
+    #[test]
+    fn bombs() {
+        let tilemap = Tilemap::new(
+            "
+=====
+=pb =
+= b =
+=====
+",
+            TILE_SIZE,
+        )
+        .expect("should return correct tilemap");
+        let arena = Arena::from_tilemap(tilemap).expect("should create arena");
+        assert_eq!(
+            arena.bombs,
+            vec![
+                TilePosition::new(2, 1, CENTER, CENTER),
+                TilePosition::new(2, 2, CENTER, CENTER),
+            ]
+        );
+    }
+
+    #[test]
+    fn patrol_routes() {
+        let tilemap = Tilemap::new(
+            "
+======
+=p   =
+======
+",
+            TILE_SIZE,
+        )
+        .expect("should return correct tilemap");
+        let mut arena = Arena::from_tilemap(tilemap).expect("should create arena");
+
+        let route = |waypoints: Vec<(u32, u32)>| PatrolRoute {
+            name: "route".to_string(),
+            mode: PatrolMode::Loop,
+            waypoints,
+        };
+        assert!(arena
+            .add_patrol_routes(vec![route(vec![(1, 1), (4, 1)])])
+            .is_ok());
+        assert!(arena.patrol_route("route").is_some());
+        assert!(
+            arena
+                .add_patrol_routes(vec![route(vec![(1, 1), (5, 1)])])
+                .is_err(),
+            "waypoint on wall"
+        );
+    }
+
+    #[test]
+    fn malformed_terrain() {
+        assert!(
+            Arena::from_terrain("\n====\n=  =\n====\n", TILE_SIZE).is_err(),
+            "missing player"
+        );
+        assert!(Arena::from_terrain("", TILE_SIZE).is_err(), "empty");
+        assert!(
+            Arena::from_terrain("\n====\n=p?=\n====\n", TILE_SIZE).is_err(),
+            "unknown tile"
+        );
+    }
+
+    #[test]
+    fn builtin_levels() {
+        for level in Levels::new().names() {
+            let arena = Arena::for_level(level, TILE_SIZE);
+            assert!(arena.is_ok(), "level '{}' is valid", level);
+            assert_eq!(arena.unwrap().level, level);
+        }
+    }
+
+    #[test]
+    fn level_file() {
+        let path = std::env::temp_dir().join("batufo-arena-level-file.txt");
+        fs::write(&path, "\n=====\n=p  =\n=====\n").expect("should write level");
+        let level = path.to_str().expect("valid path");
+
+        let arena = Arena::for_level(level, TILE_SIZE).expect("should load level file");
+        assert_eq!(arena.level, level);
+        assert_eq!(arena.floor_tiles.len(), 3);
+        assert!(arena.is_floor_tile((3, 1)));
+        assert!(!arena.is_floor_tile((4, 1)), "wall");
+
+        fs::remove_file(&path).expect("should remove level");
+        assert!(Arena::for_level(level, TILE_SIZE).is_err(), "missing file");
+    }
+
+    #[test]
+    fn level_order() {
+        let levels = Levels::new();
+        assert_eq!(levels.next_level("face off"), Some("practice arena"));
+        assert_eq!(levels.next_level("unknown"), None);
+
+        let mut level = "face off";
+        for _ in levels.names() {
+            level = levels.next_level(level).expect("should have next level");
+        }
+        assert_eq!(level, "face off", "starts over after the last level");
+    }
 }
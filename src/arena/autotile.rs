@@ -0,0 +1,465 @@
+use std::{collections::HashMap, error::Error};
+
+use serde::Deserialize;
+
+use crate::arena::Arena;
+
+/// Offsets of the north, east, south and west neighbours, going clockwise, rows grow towards north
+const SIDES: [(i64, i64); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+/// Offsets of the north east, south east, south west and north west neighbours, each of them
+/// sits between the side with the same index and the next side
+const CORNERS: [(i64, i64); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Surface {
+    Open,
+    Wall,
+    Hole,
+}
+
+/// Lookup of walls and holes by column and row, treating everything outside the arena as open.
+pub struct SurfaceGrid {
+    ncols: u32,
+    nrows: u32,
+    surfaces: Vec<Surface>,
+}
+
+impl SurfaceGrid {
+    pub fn new(arena: &Arena) -> Self {
+        let mut surfaces = vec![Surface::Open; (arena.ncols * arena.nrows) as usize];
+        for tile in arena.walls.iter() {
+            surfaces[tile.tile_idx(arena.ncols) as usize] = Surface::Wall;
+        }
+        for tile in arena.holes.iter() {
+            surfaces[tile.tile_idx(arena.ncols) as usize] = Surface::Hole;
+        }
+        Self {
+            ncols: arena.ncols,
+            nrows: arena.nrows,
+            surfaces,
+        }
+    }
+
+    fn surface(&self, col: i64, row: i64) -> Surface {
+        if col < 0 || row < 0 || col >= self.ncols as i64 || row >= self.nrows as i64 {
+            return Surface::Open;
+        }
+        self.surfaces[(row as u32 * self.ncols + col as u32) as usize]
+    }
+
+    pub fn is_wall(&self, col: i64, row: i64) -> bool {
+        self.surface(col, row) == Surface::Wall
+    }
+
+    pub fn is_hole(&self, col: i64, row: i64) -> bool {
+        self.surface(col, row) == Surface::Hole
+    }
+
+    /// Bits 0 to 3 are set for the sides, see [SIDES], whose neighbour matches.
+    fn side_mask(&self, col: u32, row: u32, matches: impl Fn(i64, i64) -> bool) -> u8 {
+        SIDES
+            .iter()
+            .enumerate()
+            .filter(|(_, (dc, dr))| matches(col as i64 + dc, row as i64 + dr))
+            .fold(0, |mask, (side, _)| mask | 1 << side)
+    }
+}
+
+fn first_side(mask: u8) -> u8 {
+    mask.trailing_zeros() as u8
+}
+
+/// Variants of floor tiles depending on the walls and holes next to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloorKind {
+    Open,
+    /// Wall on one side
+    Edge,
+    /// Walls on two sides meeting in a corner
+    Corner,
+    /// Walls on two opposite sides
+    Corridor,
+    /// Walls on all sides but one
+    DeadEnd,
+    /// Walls on all sides
+    Enclosed,
+    /// Hole on at least one side, takes precedence over walls
+    HoleRim,
+}
+
+/// Floor tile variant together with how it is rotated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloorTile {
+    pub kind: FloorKind,
+    /// Clockwise quarter turns of the atlas cell, starting with its top facing north.
+    /// Edges and hole rims face the side they border, corners face the first of their two
+    /// sides going clockwise and dead ends face their open side.
+    pub quarter_turns: u8,
+}
+
+impl FloorTile {
+    fn new(kind: FloorKind, quarter_turns: u8) -> Self {
+        Self {
+            kind,
+            quarter_turns,
+        }
+    }
+}
+
+/// Picks the floor variant of the tile at `col`, `row` from its orthogonal neighbours.
+pub fn autotile(grid: &SurfaceGrid, col: u32, row: u32) -> FloorTile {
+    let holes = grid.side_mask(col, row, |col, row| grid.is_hole(col, row));
+    let walls = grid.side_mask(col, row, |col, row| grid.is_wall(col, row));
+
+    if holes != 0 {
+        return FloorTile::new(FloorKind::HoleRim, first_side(holes));
+    }
+    match walls.count_ones() {
+        0 => FloorTile::new(FloorKind::Open, 0),
+        1 => FloorTile::new(FloorKind::Edge, first_side(walls)),
+        2 => match (0..4).find(|side| walls == (1 << side | 1 << ((side + 1) % 4))) {
+            Some(side) => FloorTile::new(FloorKind::Corner, side),
+            None => FloorTile::new(FloorKind::Corridor, first_side(walls)),
+        },
+        3 => FloorTile::new(FloorKind::DeadEnd, first_side(!walls & 0b1111)),
+        _ => FloorTile::new(FloorKind::Enclosed, 0),
+    }
+}
+
+/// Shapes of walls depending on the walls next to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum WallKind {
+    /// No walls on any side
+    Pillar,
+    /// Wall on one side
+    EndCap,
+    /// Walls on two opposite sides
+    Straight,
+    /// Walls on two sides meeting in a corner
+    Corner,
+    /// Walls on three sides
+    Tee,
+    /// Walls on all sides
+    Cross,
+    /// Linked to walls diagonally with a wall on at most one side, rendered as an angled wall
+    Diagonal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallTile {
+    pub kind: WallKind,
+    /// Bits 0 to 3 are set for walls on the north, east, south and west side
+    pub sides: u8,
+    /// Bits 0 to 3 are set for walls at the north east, south east, south west and north west
+    /// corner which aren't already connected via one of the two sides next to the corner
+    pub diagonals: u8,
+}
+
+impl WallTile {
+    pub fn side_offsets(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        SIDES
+            .iter()
+            .enumerate()
+            .filter(move |(side, _)| self.sides & 1 << side != 0)
+            .map(|(_, offset)| *offset)
+    }
+
+    pub fn diagonal_offsets(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        CORNERS
+            .iter()
+            .enumerate()
+            .filter(move |(corner, _)| self.diagonals & 1 << corner != 0)
+            .map(|(_, offset)| *offset)
+    }
+}
+
+/// Picks the shape of the wall at `col`, `row` from its eight neighbours.
+pub fn autotile_wall(grid: &SurfaceGrid, col: u32, row: u32) -> WallTile {
+    let sides = grid.side_mask(col, row, |col, row| grid.is_wall(col, row));
+    let diagonals = CORNERS
+        .iter()
+        .enumerate()
+        .filter(|(corner, (dc, dr))| {
+            let next = (corner + 1) % 4;
+            sides & (1 << corner | 1 << next) == 0 && grid.is_wall(col as i64 + dc, row as i64 + dr)
+        })
+        .fold(0, |mask, (corner, _)| mask | 1 << corner);
+
+    let kind = match sides.count_ones() {
+        _ if diagonals != 0 && sides.count_ones() <= 1 => WallKind::Diagonal,
+        0 => WallKind::Pillar,
+        1 => WallKind::EndCap,
+        2 if sides == 0b0101 || sides == 0b1010 => WallKind::Straight,
+        2 => WallKind::Corner,
+        3 => WallKind::Tee,
+        _ => WallKind::Cross,
+    };
+    WallTile {
+        kind,
+        sides,
+        diagonals,
+    }
+}
+
+/// Portion of a cell trimmed off each side to keep neighbouring cells from bleeding in
+const CELL_INSET: f32 = 0.01;
+
+/// Cells, as column and row from the top left, of `floor-tiles.png` to use for each variant.
+/// The atlas has no dedicated edge art, so darker and cracked plates are used near walls and holes.
+const FLOOR_VARIANTS: &[(FloorKind, &[(u32, u32)])] = &[
+    (
+        FloorKind::Open,
+        &[
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (4, 1),
+            (5, 1),
+            (6, 1),
+            (7, 1),
+            (0, 5),
+            (1, 5),
+            (2, 5),
+            (4, 5),
+            (5, 5),
+            (1, 6),
+            (6, 6),
+        ],
+    ),
+    (FloorKind::Edge, &[(0, 7), (1, 7), (2, 7), (3, 7)]),
+    (FloorKind::Corner, &[(4, 7), (5, 7)]),
+    (FloorKind::Corridor, &[(0, 3), (1, 3)]),
+    (FloorKind::DeadEnd, &[(6, 3), (7, 3)]),
+    (FloorKind::Enclosed, &[(3, 0)]),
+    (FloorKind::HoleRim, &[(2, 4), (3, 4), (3, 3), (6, 4)]),
+];
+
+/// Maps floor variants to cells of the floor tiles atlas.
+#[derive(Debug)]
+pub struct FloorAtlas {
+    rows: u32,
+    cols: u32,
+    variants: HashMap<FloorKind, Vec<(u32, u32)>>,
+}
+
+impl FloorAtlas {
+    /// Creates the atlas of the builtin floor tiles texture split into `rows` by `cols` cells.
+    pub fn new(rows: u32, cols: u32) -> Result<FloorAtlas, Box<dyn Error>> {
+        let mut variants = HashMap::new();
+        for (kind, cells) in FLOOR_VARIANTS {
+            if let Some((col, row)) = cells.iter().find(|(col, row)| *col >= cols || *row >= rows) {
+                return Err(format!(
+                    "floor atlas cell ({}, {}) of {:?} is outside the {}x{} atlas",
+                    col, row, kind, cols, rows
+                )
+                .into());
+            }
+            variants.insert(*kind, cells.to_vec());
+        }
+        Ok(FloorAtlas {
+            rows,
+            cols,
+            variants,
+        })
+    }
+
+    /// Atlas cell of the tile at `col`, `row`, varying between tiles of the same kind.
+    pub fn cell(&self, tile: &FloorTile, col: u32, row: u32) -> (u32, u32) {
+        let cells = &self.variants[&tile.kind];
+        let hash = col.wrapping_mul(73_856_093) ^ row.wrapping_mul(19_349_663);
+        cells[hash as usize % cells.len()]
+    }
+
+    /// Uvs of the south west, south east, north east and north west corners of the tile.
+    pub fn uvs(&self, tile: &FloorTile, col: u32, row: u32) -> [[f32; 2]; 4] {
+        let (cell_col, cell_row) = self.cell(tile, col, row);
+        let (width, height) = (1.0 / self.cols as f32, 1.0 / self.rows as f32);
+        let u0 = (cell_col as f32 + CELL_INSET) * width;
+        let u1 = (cell_col as f32 + 1.0 - CELL_INSET) * width;
+        let v0 = (cell_row as f32 + CELL_INSET) * height;
+        let v1 = (cell_row as f32 + 1.0 - CELL_INSET) * height;
+        let corners = [[u0, v1], [u1, v1], [u1, v0], [u0, v0]];
+        // turning the cell clockwise moves each corner onto the next one counter clockwise
+        let turns = tile.quarter_turns as usize;
+        [
+            corners[turns % 4],
+            corners[(turns + 1) % 4],
+            corners[(turns + 2) % 4],
+            corners[(turns + 3) % 4],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::tilemap::Tilemap;
+
+    fn grid(terrain: &str) -> SurfaceGrid {
+        let tilemap = Tilemap::new(terrain, 1).expect("should return correct tilemap");
+        SurfaceGrid::new(&Arena::from_tilemap(tilemap).expect("should create arena"))
+    }
+
+    fn tile(kind: FloorKind, quarter_turns: u8) -> FloorTile {
+        FloorTile::new(kind, quarter_turns)
+    }
+
+    #[test]
+    fn room() {
+        let grid = grid(
+            "
+=====
+=   =
+= p =
+=   =
+=====
+",
+        );
+        assert_eq!(autotile(&grid, 2, 2), tile(FloorKind::Open, 0));
+        assert_eq!(autotile(&grid, 2, 3), tile(FloorKind::Edge, 0), "north");
+        assert_eq!(autotile(&grid, 3, 2), tile(FloorKind::Edge, 1), "east");
+        assert_eq!(autotile(&grid, 2, 1), tile(FloorKind::Edge, 2), "south");
+        assert_eq!(autotile(&grid, 1, 2), tile(FloorKind::Edge, 3), "west");
+        assert_eq!(
+            autotile(&grid, 3, 3),
+            tile(FloorKind::Corner, 0),
+            "north east"
+        );
+        assert_eq!(
+            autotile(&grid, 3, 1),
+            tile(FloorKind::Corner, 1),
+            "south east"
+        );
+        assert_eq!(
+            autotile(&grid, 1, 1),
+            tile(FloorKind::Corner, 2),
+            "south west"
+        );
+        assert_eq!(
+            autotile(&grid, 1, 3),
+            tile(FloorKind::Corner, 3),
+            "north west"
+        );
+    }
+
+    #[test]
+    fn corridors() {
+        let grid = grid(
+            "
+=====
+=p  =
+=== =
+  =p=
+  ===
+",
+        );
+        assert_eq!(
+            autotile(&grid, 1, 3),
+            tile(FloorKind::DeadEnd, 1),
+            "opens east"
+        );
+        assert_eq!(autotile(&grid, 2, 3), tile(FloorKind::Corridor, 0));
+        assert_eq!(autotile(&grid, 3, 2), tile(FloorKind::Corridor, 1));
+        assert_eq!(
+            autotile(&grid, 3, 1),
+            tile(FloorKind::DeadEnd, 0),
+            "opens north"
+        );
+    }
+
+    #[test]
+    fn hole_rims() {
+        let grid = grid(
+            "
+=====
+=p x=
+=====
+",
+        );
+        assert_eq!(
+            autotile(&grid, 2, 1),
+            tile(FloorKind::HoleRim, 1),
+            "hole wins over walls"
+        );
+        assert_eq!(autotile(&grid, 1, 1), tile(FloorKind::DeadEnd, 1));
+    }
+
+    #[test]
+    fn wall_shapes() {
+        let pillar = grid("=p");
+        assert_eq!(autotile_wall(&pillar, 0, 0).kind, WallKind::Pillar);
+
+        let grid = grid(
+            "
+=======
+=p =  =
+= === =
+=  =  =
+=======
+",
+        );
+        let kind = |col, row| autotile_wall(&grid, col, row).kind;
+        assert_eq!(kind(3, 2), WallKind::Cross);
+        assert_eq!(kind(2, 2), WallKind::EndCap);
+        assert_eq!(kind(3, 1), WallKind::Straight);
+        assert_eq!(kind(0, 0), WallKind::Corner);
+        assert_eq!(kind(3, 0), WallKind::Tee);
+        assert_eq!(kind(6, 2), WallKind::Straight);
+    }
+
+    #[test]
+    fn diagonal_walls() {
+        let grid = grid(
+            "
+====
+=p =
+ = =
+  ==
+",
+        );
+        let wall = autotile_wall(&grid, 1, 1);
+        assert_eq!(wall.kind, WallKind::Diagonal);
+        assert_eq!(wall.sides, 0);
+        assert_eq!(wall.diagonals, 0b1010, "north west and south east");
+        assert_eq!(
+            wall.diagonal_offsets().collect::<Vec<_>>(),
+            vec![(1, -1), (-1, 1)]
+        );
+
+        let wall = autotile_wall(&grid, 0, 2);
+        assert_eq!(wall.kind, WallKind::Diagonal, "wall on one side");
+        assert_eq!(wall.side_offsets().collect::<Vec<_>>(), vec![(0, 1)]);
+        assert_eq!(wall.diagonals, 0b0010, "south east");
+
+        let wall = autotile_wall(&grid, 2, 0);
+        assert_eq!(wall.kind, WallKind::Diagonal);
+        assert_eq!(wall.diagonals, 0b1000, "north west");
+        assert_eq!(wall.sides, 0b0010, "east");
+
+        assert_eq!(autotile_wall(&grid, 0, 3).kind, WallKind::Corner);
+    }
+
+    #[test]
+    fn atlas_uvs() {
+        let atlas = FloorAtlas::new(8, 8).expect("builtin variants fit 8x8 atlas");
+        let enclosed = tile(FloorKind::Enclosed, 0);
+        assert_eq!(atlas.cell(&enclosed, 5, 7), (3, 0));
+
+        let uvs = atlas.uvs(&enclosed, 5, 7);
+        let [south_west, south_east, north_east, north_west] = uvs;
+        assert!(
+            south_west[0] > 0.375 && south_east[0] < 0.5,
+            "inside cell column"
+        );
+        assert!(
+            north_west[1] > 0.0 && south_west[1] < 0.125,
+            "inside cell row"
+        );
+        assert_eq!(north_east, [south_east[0], north_west[1]]);
+
+        let turned = atlas.uvs(&tile(FloorKind::Enclosed, 1), 5, 7);
+        assert_eq!(turned, [south_east, north_east, north_west, south_west]);
+
+        assert!(FloorAtlas::new(4, 8).is_err(), "cells outside atlas");
+    }
+}
@@ -64,5 +64,8 @@ pub fn level_coliseum() -> &'static str {
                   ====          2           ====
                       =====            =====
                            ============
+
+[theme]
+walls bunker
 "
 }
@@ -57,5 +57,15 @@ pub fn level_face_off() -> &'static str {
          =                   =
         =                     =
         =======================
+
+[routes]
+# name mode waypoints as col,row with row 0 at the bottom of the terrain
+lower-hall loop 20,20 32,20 32,17 8,17 8,20
+
+[spawns]
+# archetype col,row route
+rolling-tower 20,20 lower-hall
+gun-tower 20,40
+bank-tower 66,28
 "
 }
@@ -0,0 +1,614 @@
+use std::collections::BTreeMap;
+
+use crate::arena::{
+    autotile, autotile_wall, Arena, FloorAtlas, SurfaceGrid, WallKind, WallTheme, WallTile,
+    WallVariant,
+};
+
+/// Width and height of the square chunks the arena geometry is split into, in tiles
+pub const CHUNK_SIZE: u32 = 16;
+/// Portion of a tile covered by its floor plate, leaving gaps between the plates
+pub const FLOOR_TILE_SIZE: f32 = 0.92;
+/// Height of the floor plates relative to the tile size
+pub const FLOOR_HEIGHT: f32 = 0.05;
+
+/// Triangle list of quads with normals and uvs, independent of any renderer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Number of quads, each made up of two triangles.
+    pub fn face_count(&self) -> usize {
+        self.indices.len() / 6
+    }
+
+    /// Adds a quad from corners given counter clockwise starting at the bottom left, as seen
+    /// from the outside. The winding is fixed up so that the quad faces towards `normal`.
+    fn add_quad(&mut self, corners: [[f32; 3]; 4], normal: [f32; 3], uvs: [[f32; 2]; 4]) {
+        let facing = facing(corners[0], corners[1], corners[2], normal);
+
+        let first = self.positions.len() as u32;
+        self.positions.extend_from_slice(&corners);
+        self.normals.extend_from_slice(&[normal; 4]);
+        self.uvs.extend_from_slice(&uvs);
+        if facing >= 0.0 {
+            self.indices.extend_from_slice(&[
+                first,
+                first + 1,
+                first + 2,
+                first,
+                first + 2,
+                first + 3,
+            ]);
+        } else {
+            self.indices.extend_from_slice(&[
+                first,
+                first + 2,
+                first + 1,
+                first,
+                first + 3,
+                first + 2,
+            ]);
+        }
+    }
+}
+
+/// Uvs for a quad extending `u` by `v` tiles, which repeats textures once per tile.
+fn tiled_uvs(u: f32, v: f32) -> [[f32; 2]; 4] {
+    [[0.0, v], [u, v], [u, 0.0], [0.0, 0.0]]
+}
+
+/// Positive if the triangle `a`, `b`, `c` is wound counter clockwise when seen from `normal`.
+fn facing(a: [f32; 3], b: [f32; 3], c: [f32; 3], normal: [f32; 3]) -> f32 {
+    let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        ab[1] * ac[2] - ab[2] * ac[1],
+        ab[2] * ac[0] - ab[0] * ac[2],
+        ab[0] * ac[1] - ab[1] * ac[0],
+    ];
+    cross[0] * normal[0] + cross[1] * normal[1] + cross[2] * normal[2]
+}
+
+/// Floor and wall meshes of one chunk of the arena, in world coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkGeometry {
+    /// Column and row of the chunk, counted in chunks
+    pub chunk: (u32, u32),
+    pub floor: MeshData,
+    /// Wall meshes by the texture they are rendered with
+    pub walls: BTreeMap<String, MeshData>,
+}
+
+impl ChunkGeometry {
+    fn walls_mesh(&mut self, texture: &str) -> &mut MeshData {
+        self.walls.entry(texture.to_string()).or_default()
+    }
+}
+
+/// Converts a point given in tiles, with rows growing up, to world coordinates.
+fn world(x: f32, y: f32, height: f32, tile_size: f32) -> [f32; 3] {
+    [x * tile_size, height, -y * tile_size]
+}
+
+/// Builds the arena geometry split into chunks of [CHUNK_SIZE] tiles.
+/// Floor tiles are mapped to the atlas cell picked by [autotile]. Walls are shaped by
+/// [autotile_wall] and rendered with the matching variant of the `wall_theme`, one mesh per
+/// texture. Adjacent walls of a row sharing a variant are merged into one box and faces hidden by
+/// neighbouring walls are dropped. Chunks without any geometry are omitted.
+pub fn arena_geometry(
+    arena: &Arena,
+    tile_size: f32,
+    floor_atlas: &FloorAtlas,
+    wall_theme: &WallTheme,
+) -> Vec<ChunkGeometry> {
+    let mut chunks: BTreeMap<(u32, u32), ChunkGeometry> = BTreeMap::new();
+    let grid = SurfaceGrid::new(arena);
+
+    for tile in arena.floor_tiles.iter() {
+        let uvs = floor_atlas.uvs(&autotile(&grid, tile.col, tile.row), tile.col, tile.row);
+        let geometry = chunk_at(&mut chunks, tile.col, tile.row);
+        add_floor_tile(&mut geometry.floor, tile.col, tile.row, tile_size, uvs);
+    }
+
+    let layout = WallLayout::new(arena, &grid, wall_theme);
+    for row in 0..arena.nrows {
+        let mut col = 0;
+        while col < arena.ncols {
+            let (wall, variant) = match layout.get(col as i64, row as i64) {
+                Some(wall) => wall,
+                None => {
+                    col += 1;
+                    continue;
+                }
+            };
+            let geometry = chunk_at(&mut chunks, col, row);
+            let mesh = geometry.walls_mesh(&variant.texture);
+            match wall.kind {
+                WallKind::Pillar => {
+                    add_post(
+                        mesh,
+                        (col as f32 + 0.5, row as f32 + 0.5),
+                        variant,
+                        tile_size,
+                    );
+                    col += 1;
+                }
+                WallKind::Diagonal => {
+                    add_angled_wall(mesh, col, row, wall, variant, tile_size);
+                    col += 1;
+                }
+                _ => {
+                    // extend the run to the right, but never past the end of the chunk
+                    let start = col;
+                    let chunk_end = (start / CHUNK_SIZE + 1) * CHUNK_SIZE;
+                    while col < arena.ncols
+                        && col < chunk_end
+                        && layout.block(col as i64, row as i64) == Some(variant)
+                    {
+                        col += 1;
+                    }
+                    add_wall_run(mesh, &layout, row, (start, col), variant, tile_size);
+                }
+            }
+        }
+    }
+
+    chunks.into_iter().map(|(_, geometry)| geometry).collect()
+}
+
+/// Shape and variant of each wall, by column and row.
+struct WallLayout<'a> {
+    ncols: u32,
+    nrows: u32,
+    walls: Vec<Option<(WallTile, &'a WallVariant)>>,
+}
+
+impl<'a> WallLayout<'a> {
+    fn new(arena: &Arena, grid: &SurfaceGrid, theme: &'a WallTheme) -> Self {
+        let mut walls = vec![None; (arena.ncols * arena.nrows) as usize];
+        for tile in arena.walls.iter() {
+            let wall = autotile_wall(grid, tile.col, tile.row);
+            walls[tile.tile_idx(arena.ncols) as usize] = Some((wall, theme.variant(wall.kind)));
+        }
+        Self {
+            ncols: arena.ncols,
+            nrows: arena.nrows,
+            walls,
+        }
+    }
+
+    fn get(&self, col: i64, row: i64) -> Option<(&WallTile, &'a WallVariant)> {
+        if col < 0 || row < 0 || col >= self.ncols as i64 || row >= self.nrows as i64 {
+            return None;
+        }
+        let wall = self.walls[(row as u32 * self.ncols + col as u32) as usize].as_ref()?;
+        Some((&wall.0, wall.1))
+    }
+
+    /// Variant of the wall if it fills its entire tile, which pillars and angled walls don't.
+    fn block(&self, col: i64, row: i64) -> Option<&'a WallVariant> {
+        match self.get(col, row)? {
+            (
+                WallTile {
+                    kind: WallKind::Pillar,
+                    ..
+                },
+                _,
+            )
+            | (
+                WallTile {
+                    kind: WallKind::Diagonal,
+                    ..
+                },
+                _,
+            ) => None,
+            (_, variant) => Some(variant),
+        }
+    }
+
+    /// Whether a block at `col`, `row` hides the face of a neighbouring wall of `height`.
+    fn hides(&self, col: i64, row: i64, height: f32) -> bool {
+        self.block(col, row)
+            .map_or(false, |variant| variant.height >= height)
+    }
+}
+
+fn chunk_at(
+    chunks: &mut BTreeMap<(u32, u32), ChunkGeometry>,
+    col: u32,
+    row: u32,
+) -> &mut ChunkGeometry {
+    let chunk = (col / CHUNK_SIZE, row / CHUNK_SIZE);
+    chunks.entry(chunk).or_insert_with(|| ChunkGeometry {
+        chunk,
+        ..Default::default()
+    })
+}
+
+fn add_floor_tile(mesh: &mut MeshData, col: u32, row: u32, tile_size: f32, uvs: [[f32; 2]; 4]) {
+    let inset = (1.0 - FLOOR_TILE_SIZE) / 2.0;
+    let (x0, x1) = (col as f32 + inset, col as f32 + 1.0 - inset);
+    let (y0, y1) = (row as f32 + inset, row as f32 + 1.0 - inset);
+    let h = FLOOR_HEIGHT * tile_size;
+    mesh.add_quad(
+        [
+            world(x0, y0, h, tile_size),
+            world(x1, y0, h, tile_size),
+            world(x1, y1, h, tile_size),
+            world(x0, y1, h, tile_size),
+        ],
+        [0.0, 1.0, 0.0],
+        uvs,
+    );
+}
+
+/// Adds a box covering the walls of `row` from column `start` up to, excluding, `end`.
+fn add_wall_run(
+    mesh: &mut MeshData,
+    layout: &WallLayout,
+    row: u32,
+    (start, end): (u32, u32),
+    variant: &WallVariant,
+    tile_size: f32,
+) {
+    let height = variant.height;
+    let h = height * tile_size;
+    let (x0, x1) = (start as f32, end as f32);
+    let (y0, y1) = (row as f32, row as f32 + 1.0);
+    let len = x1 - x0;
+
+    mesh.add_quad(
+        [
+            world(x0, y0, h, tile_size),
+            world(x1, y0, h, tile_size),
+            world(x1, y1, h, tile_size),
+            world(x0, y1, h, tile_size),
+        ],
+        [0.0, 1.0, 0.0],
+        tiled_uvs(len, 1.0),
+    );
+    if !layout.hides(start as i64 - 1, row as i64, height) {
+        mesh.add_quad(
+            [
+                world(x0, y0, 0.0, tile_size),
+                world(x0, y1, 0.0, tile_size),
+                world(x0, y1, h, tile_size),
+                world(x0, y0, h, tile_size),
+            ],
+            [-1.0, 0.0, 0.0],
+            tiled_uvs(1.0, height),
+        );
+    }
+    if !layout.hides(end as i64, row as i64, height) {
+        mesh.add_quad(
+            [
+                world(x1, y0, 0.0, tile_size),
+                world(x1, y1, 0.0, tile_size),
+                world(x1, y1, h, tile_size),
+                world(x1, y0, h, tile_size),
+            ],
+            [1.0, 0.0, 0.0],
+            tiled_uvs(1.0, height),
+        );
+    }
+
+    // rows grow towards negative z, so the row below faces positive z
+    for (neighbour_row, y, normal) in [
+        (row as i64 - 1, y0, [0.0, 0.0, 1.0]),
+        (row as i64 + 1, y1, [0.0, 0.0, -1.0]),
+    ]
+    .iter()
+    {
+        let hidden = |col: u32| layout.hides(col as i64, *neighbour_row, height);
+        let mut col = start;
+        while col < end {
+            if hidden(col) {
+                col += 1;
+                continue;
+            }
+            let open_start = col;
+            while col < end && !hidden(col) {
+                col += 1;
+            }
+            let (a, b) = (open_start as f32, col as f32);
+            mesh.add_quad(
+                [
+                    world(a, *y, 0.0, tile_size),
+                    world(b, *y, 0.0, tile_size),
+                    world(b, *y, h, tile_size),
+                    world(a, *y, h, tile_size),
+                ],
+                *normal,
+                tiled_uvs(b - a, height),
+            );
+        }
+    }
+}
+
+/// Adds a square post of the variant's thickness around `center`, given in tiles.
+fn add_post(mesh: &mut MeshData, (x, y): (f32, f32), variant: &WallVariant, tile_size: f32) {
+    let half = variant.thickness / 2.0;
+    let h = variant.height * tile_size;
+    let (x0, x1, y0, y1) = (x - half, x + half, y - half, y + half);
+    mesh.add_quad(
+        [
+            world(x0, y0, h, tile_size),
+            world(x1, y0, h, tile_size),
+            world(x1, y1, h, tile_size),
+            world(x0, y1, h, tile_size),
+        ],
+        [0.0, 1.0, 0.0],
+        tiled_uvs(variant.thickness, variant.thickness),
+    );
+    for (a, b, normal) in [
+        ((x0, y0), (x0, y1), [-1.0, 0.0, 0.0]),
+        ((x1, y0), (x1, y1), [1.0, 0.0, 0.0]),
+        ((x0, y0), (x1, y0), [0.0, 0.0, 1.0]),
+        ((x0, y1), (x1, y1), [0.0, 0.0, -1.0]),
+    ]
+    .iter()
+    {
+        mesh.add_quad(
+            [
+                world(a.0, a.1, 0.0, tile_size),
+                world(b.0, b.1, 0.0, tile_size),
+                world(b.0, b.1, h, tile_size),
+                world(a.0, a.1, h, tile_size),
+            ],
+            *normal,
+            tiled_uvs(variant.thickness, variant.height),
+        );
+    }
+}
+
+/// Adds the top and long sides of a wall of the variant's thickness running from `from` to `to`,
+/// both given in tiles. The ends are left open as they meet other walls.
+fn add_slab(
+    mesh: &mut MeshData,
+    from: (f32, f32),
+    to: (f32, f32),
+    variant: &WallVariant,
+    tile_size: f32,
+) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let len = (dx * dx + dy * dy).sqrt();
+    let half = variant.thickness / 2.0;
+    // points to the left of the direction of the slab
+    let (nx, ny) = (-dy / len, dx / len);
+    let left = |(x, y): (f32, f32)| (x + nx * half, y + ny * half);
+    let right = |(x, y): (f32, f32)| (x - nx * half, y - ny * half);
+    let h = variant.height * tile_size;
+
+    let corners = [right(from), right(to), left(to), left(from)];
+    mesh.add_quad(
+        [
+            world(corners[0].0, corners[0].1, h, tile_size),
+            world(corners[1].0, corners[1].1, h, tile_size),
+            world(corners[2].0, corners[2].1, h, tile_size),
+            world(corners[3].0, corners[3].1, h, tile_size),
+        ],
+        [0.0, 1.0, 0.0],
+        tiled_uvs(len, variant.thickness),
+    );
+    for (a, b, (nx, ny)) in [
+        (left(from), left(to), (nx, ny)),
+        (right(from), right(to), (-nx, -ny)),
+    ]
+    .iter()
+    {
+        mesh.add_quad(
+            [
+                world(a.0, a.1, 0.0, tile_size),
+                world(b.0, b.1, 0.0, tile_size),
+                world(b.0, b.1, h, tile_size),
+                world(a.0, a.1, h, tile_size),
+            ],
+            [*nx, 0.0, -*ny],
+            tiled_uvs(len, variant.height),
+        );
+    }
+}
+
+/// Adds a post in the center of the tile with slabs running to each neighbouring wall. Slabs to
+/// diagonal neighbours meet theirs at the shared corner, which turns staircases of walls into
+/// angled walls.
+fn add_angled_wall(
+    mesh: &mut MeshData,
+    col: u32,
+    row: u32,
+    wall: &WallTile,
+    variant: &WallVariant,
+    tile_size: f32,
+) {
+    let center = (col as f32 + 0.5, row as f32 + 0.5);
+    add_post(mesh, center, variant, tile_size);
+    let sides = wall
+        .side_offsets()
+        .map(|(dc, dr)| (dc as f32 * 0.5, dr as f32 * 0.5));
+    let corners = wall
+        .diagonal_offsets()
+        .map(|(dc, dr)| (dc as f32 * 0.5, dr as f32 * 0.5));
+    for (dx, dy) in sides.chain(corners) {
+        add_slab(
+            mesh,
+            center,
+            (center.0 + dx, center.1 + dy),
+            variant,
+            tile_size,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::tilemap::Tilemap;
+
+    fn themed_geometry(terrain: &str, theme: &WallTheme) -> Vec<ChunkGeometry> {
+        let tilemap = Tilemap::new(terrain, 1).expect("should return correct tilemap");
+        let atlas = FloorAtlas::new(8, 8).expect("should create floor atlas");
+        let arena = Arena::from_tilemap(tilemap).expect("should create arena");
+        arena_geometry(&arena, 1.0, &atlas, theme)
+    }
+
+    fn geometry(terrain: &str) -> Vec<ChunkGeometry> {
+        themed_geometry(terrain, &WallTheme::default())
+    }
+
+    fn faces(walls: &BTreeMap<String, MeshData>) -> usize {
+        walls.values().map(|mesh| mesh.face_count()).sum()
+    }
+
+    fn wall_faces(chunks: &[ChunkGeometry]) -> usize {
+        chunks.iter().map(|chunk| faces(&chunk.walls)).sum()
+    }
+
+    #[test]
+    fn single_wall() {
+        let chunks = geometry("=p");
+        assert_eq!(chunks.len(), 1);
+        let walls = &chunks[0].walls[&WallVariant::default().texture];
+        assert_eq!(walls.face_count(), 5, "top and four sides");
+        assert_eq!(walls.vertex_count(), 20);
+        assert_eq!(chunks[0].floor.face_count(), 1, "floor below player");
+        assert_eq!(chunks[0].floor.vertex_count(), 4);
+    }
+
+    #[test]
+    fn merged_walls() {
+        let chunks = geometry("=p===");
+        assert_eq!(wall_faces(&chunks), 5 + 5, "one box for each run of walls");
+
+        let chunks = geometry(
+            "
+=p==
+  ==
+",
+        );
+        assert_eq!(
+            wall_faces(&chunks),
+            5 + 4 + 4,
+            "drops the faces between the rows of the block"
+        );
+
+        let chunks = geometry(
+            "
+=p===
+  = =
+",
+        );
+        // the upper run keeps the part of its south face above the gap
+        assert_eq!(wall_faces(&chunks), 5 + 5 + 4 + 4);
+    }
+
+    #[test]
+    fn chunks() {
+        let terrain = format!("=p{}", "=".repeat(CHUNK_SIZE as usize));
+        let chunks = geometry(&terrain);
+        assert_eq!(chunks.len(), 2, "walls spill into second chunk");
+        assert_eq!(chunks[0].chunk, (0, 0));
+        assert_eq!(chunks[1].chunk, (1, 0));
+        assert_eq!(
+            faces(&chunks[0].walls),
+            5 + 4,
+            "run is split at the chunk border without faces between the parts"
+        );
+        assert_eq!(faces(&chunks[1].walls), 4);
+        assert!(chunks[1].floor.is_empty());
+    }
+
+    #[test]
+    fn wall_variants() {
+        let mut theme = WallTheme::default();
+        theme.variants.insert(
+            WallKind::EndCap,
+            WallVariant {
+                height: 1.2,
+                ..Default::default()
+            },
+        );
+        let chunks = themed_geometry("=p====", &theme);
+        // the lower straight part between the taller end caps loses its west and east faces,
+        // while the end caps keep theirs
+        assert_eq!(wall_faces(&chunks), 5 + 5 + 3 + 5);
+    }
+
+    #[test]
+    fn angled_walls() {
+        let mut theme = WallTheme::default();
+        theme.variants.insert(
+            WallKind::Diagonal,
+            WallVariant {
+                texture: "angled.png".to_string(),
+                thickness: 0.5,
+                ..Default::default()
+            },
+        );
+        let terrain = "
+=p
+ =
+";
+        let chunks = themed_geometry(terrain, &theme);
+        let walls = &chunks[0].walls;
+        assert_eq!(walls.keys().collect::<Vec<_>>(), vec!["angled.png"]);
+        // both walls get a post and a slab running to the corner they share
+        assert_eq!(faces(walls), 2 * (5 + 3));
+
+        // the tops of both slabs end at the corner (1, 1), which is at z -1 in world coordinates,
+        // each with two corners which are part of the top and one side
+        let slab_ends = walls["angled.png"]
+            .positions
+            .iter()
+            .filter(|[x, y, z]| {
+                let distance = (x - 1.0).powi(2) + (z + 1.0).powi(2);
+                *y == 0.95 && (distance - 0.25_f32.powi(2)).abs() < 0.001
+            })
+            .count();
+        assert_eq!(
+            slab_ends,
+            2 * 2 * 2,
+            "slab tops of both walls meet at the corner"
+        );
+    }
+
+    #[test]
+    fn winding() {
+        let chunks = geometry(
+            "
+====
+=p =
+ = =
+  ==
+",
+        );
+        let meshes = chunks.iter().flat_map(|chunk| chunk.walls.values());
+        for mesh in meshes.chain(chunks.iter().map(|chunk| &chunk.floor)) {
+            for triangle in mesh.indices.chunks(3) {
+                let [a, b, c] = [
+                    mesh.positions[triangle[0] as usize],
+                    mesh.positions[triangle[1] as usize],
+                    mesh.positions[triangle[2] as usize],
+                ];
+                let normal = mesh.normals[triangle[0] as usize];
+                assert!(
+                    facing(a, b, c, normal) > 0.0,
+                    "counter clockwise towards the normal"
+                );
+            }
+        }
+    }
+}
@@ -21,40 +21,55 @@ impl Level {
 
 pub struct Levels {
     levels: HashMap<&'static str, Level>,
+    /// Names in the order the levels are played
+    order: Vec<&'static str>,
 }
 
 impl Levels {
     pub fn new() -> Self {
-        let mut levels: HashMap<&'static str, Level> = HashMap::new();
-        add_level(
-            &mut levels,
-            "face off",
-            builtins::face_off::level_face_off(),
-        );
-        add_level(
-            &mut levels,
+        let mut levels = Levels {
+            levels: HashMap::new(),
+            order: Vec::new(),
+        };
+        levels.add_level("face off", builtins::face_off::level_face_off());
+        levels.add_level(
             "practice arena",
             builtins::practice_arena::level_practice_arena(),
         );
-        add_level(&mut levels, "mini", builtins::mini::level_mini());
-        add_level(
-            &mut levels,
-            "coliseum",
-            builtins::coliseum::level_coliseum(),
-        );
-        Levels { levels }
+        levels.add_level("mini", builtins::mini::level_mini());
+        levels.add_level("coliseum", builtins::coliseum::level_coliseum());
+        levels
     }
 
     #[allow(dead_code)]
-    pub fn is_valid_level(&self, level_name: &'static str) -> bool {
+    pub fn is_valid_level(&self, level_name: &str) -> bool {
         self.levels.contains_key(level_name)
     }
 
-    pub fn get_level(&self, level_name: &'static str) -> Option<&Level> {
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.levels.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn get_level(&self, level_name: &str) -> Option<&Level> {
         self.levels.get(level_name)
     }
+
+    /// Level played after the given one, starting over after the last level.
+    pub fn next_level(&self, level_name: &str) -> Option<&'static str> {
+        let idx = self.order.iter().position(|name| *name == level_name)?;
+        Some(self.order[(idx + 1) % self.order.len()])
+    }
+
+    fn add_level(&mut self, name: &'static str, terrain: &'static str) {
+        self.levels.insert(name, Level::new(name, terrain));
+        self.order.push(name);
+    }
 }
 
-fn add_level(levels: &mut HashMap<&'static str, Level>, name: &'static str, terrain: &'static str) {
-    levels.insert(name, Level::new(name, terrain));
+impl Default for Levels {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,277 @@
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatrolMode {
+    /// After the last waypoint continue with the first
+    Loop,
+    /// After the last waypoint walk the route backwards
+    PingPong,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatrolRoute {
+    pub name: String,
+    pub mode: PatrolMode,
+    /// Tile coordinates as (col, row) with row 0 at the bottom of the terrain
+    pub waypoints: Vec<(u32, u32)>,
+}
+
+/// Marks where an enemy of the given archetype is placed when the level starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemySpawn {
+    /// Name of the archetype defined in the enemy archetypes asset
+    pub archetype: String,
+    /// Tile coordinates as (col, row) with row 0 at the bottom of the terrain
+    pub tile: (u32, u32),
+    /// Name of the patrol route the enemy follows, if any
+    pub patrol_route: Option<String>,
+}
+
+/// Optional sections following the terrain of a level, separated from it by an empty line.
+///
+/// ```text
+/// [routes]
+/// # name mode waypoints
+/// lower-hall loop 20,20 32,20 32,17
+/// gate ping-pong 4,4 4,9
+///
+/// [spawns]
+/// # archetype tile route
+/// rolling-tower 20,20 lower-hall
+/// gun-tower 20,40
+///
+/// [theme]
+/// walls bunker
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LevelMetadata {
+    pub patrol_routes: Vec<PatrolRoute>,
+    pub enemy_spawns: Vec<EnemySpawn>,
+    /// Name of the wall theme defined in the wall themes asset
+    pub wall_theme: Option<String>,
+}
+
+impl LevelMetadata {
+    pub fn parse(level: &str) -> Result<LevelMetadata, Box<dyn Error>> {
+        let mut metadata = LevelMetadata::default();
+        let mut section: Option<&str> = None;
+        for line in metadata_lines(level) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = Some(&line[1..line.len() - 1]);
+                continue;
+            }
+            match section {
+                Some("routes") => {
+                    let route = parse_route(line)?;
+                    if metadata.patrol_route(&route.name).is_some() {
+                        return Err(format!("duplicate patrol route '{}'", route.name).into());
+                    }
+                    metadata.patrol_routes.push(route);
+                }
+                Some("spawns") => metadata.enemy_spawns.push(parse_spawn(line)?),
+                Some("theme") => match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    ["walls", name] => metadata.wall_theme = Some(name.to_string()),
+                    _ => return Err(format!("invalid level theme '{}'", line).into()),
+                },
+                Some(section) => {
+                    return Err(format!("unknown level metadata section '[{}]'", section).into())
+                }
+                None => {
+                    return Err(format!("level metadata '{}' is outside of a section", line).into())
+                }
+            }
+        }
+        Ok(metadata)
+    }
+
+    pub fn patrol_route(&self, name: &str) -> Option<&PatrolRoute> {
+        self.patrol_routes.iter().find(|route| route.name == name)
+    }
+}
+
+/// Lines following the terrain, which ends at the first empty line.
+fn metadata_lines(level: &str) -> impl Iterator<Item = &str> {
+    level
+        .lines()
+        .skip_while(|s| s.trim().is_empty())
+        .skip_while(|s| !s.trim().is_empty())
+}
+
+fn parse_route(line: &str) -> Result<PatrolRoute, Box<dyn Error>> {
+    let mut parts = line.split_whitespace();
+    let name = parts.next().ok_or("patrol route is missing a name")?;
+    let mode = match parts.next() {
+        Some("loop") => PatrolMode::Loop,
+        Some("ping-pong") => PatrolMode::PingPong,
+        Some(mode) => {
+            return Err(format!("patrol route '{}' has unknown mode '{}'", name, mode).into())
+        }
+        None => return Err(format!("patrol route '{}' is missing a mode", name).into()),
+    };
+    let waypoints = parts
+        .map(|waypoint| parse_col_row(waypoint).ok_or(waypoint))
+        .collect::<Result<Vec<(u32, u32)>, &str>>()
+        .map_err(|waypoint| {
+            format!(
+                "patrol route '{}' has invalid waypoint '{}', expected 'col,row'",
+                name, waypoint
+            )
+        })?;
+    if waypoints.len() < 2 {
+        return Err(format!("patrol route '{}' needs at least two waypoints", name).into());
+    }
+    Ok(PatrolRoute {
+        name: name.to_string(),
+        mode,
+        waypoints,
+    })
+}
+
+fn parse_spawn(line: &str) -> Result<EnemySpawn, Box<dyn Error>> {
+    let mut parts = line.split_whitespace();
+    let archetype = parts.next().ok_or("enemy spawn is missing an archetype")?;
+    let tile = parts.next().and_then(parse_col_row).ok_or(format!(
+        "enemy spawn '{}' needs a 'col,row' tile",
+        archetype
+    ))?;
+    let patrol_route = parts.next().map(|route| route.to_string());
+    if let Some(extra) = parts.next() {
+        return Err(format!("enemy spawn '{}' has unexpected '{}'", archetype, extra).into());
+    }
+    Ok(EnemySpawn {
+        archetype: archetype.to_string(),
+        tile,
+        patrol_route,
+    })
+}
+
+fn parse_col_row(s: &str) -> Option<(u32, u32)> {
+    let mut coords = s.split(',');
+    let col = coords.next()?.trim().parse().ok()?;
+    let row = coords.next()?.trim().parse().ok()?;
+    match coords.next() {
+        None => Some((col, row)),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TERRAIN: &str = "
+======
+= p  =
+======
+";
+
+    #[test]
+    fn no_metadata() {
+        let metadata = LevelMetadata::parse(TERRAIN).expect("should parse");
+        assert_eq!(metadata, LevelMetadata::default());
+    }
+
+    #[test]
+    fn patrol_routes() {
+        let level = format!(
+            "{}
+[routes]
+# name mode waypoints
+north loop 1,1 4,1 4,3
+gate ping-pong 2,1 3,1
+",
+            TERRAIN
+        );
+        let metadata = LevelMetadata::parse(&level).expect("should parse");
+        assert_eq!(
+            metadata.patrol_routes,
+            vec![
+                PatrolRoute {
+                    name: "north".to_string(),
+                    mode: PatrolMode::Loop,
+                    waypoints: vec![(1, 1), (4, 1), (4, 3)],
+                },
+                PatrolRoute {
+                    name: "gate".to_string(),
+                    mode: PatrolMode::PingPong,
+                    waypoints: vec![(2, 1), (3, 1)],
+                },
+            ]
+        );
+        assert!(metadata.patrol_route("gate").is_some());
+        assert!(metadata.patrol_route("south").is_none());
+    }
+
+    #[test]
+    fn enemy_spawns() {
+        let level = format!(
+            "{}
+[spawns]
+rolling-tower 1,1 north
+gun-tower 4,1
+",
+            TERRAIN
+        );
+        let metadata = LevelMetadata::parse(&level).expect("should parse");
+        assert_eq!(
+            metadata.enemy_spawns,
+            vec![
+                EnemySpawn {
+                    archetype: "rolling-tower".to_string(),
+                    tile: (1, 1),
+                    patrol_route: Some("north".to_string()),
+                },
+                EnemySpawn {
+                    archetype: "gun-tower".to_string(),
+                    tile: (4, 1),
+                    patrol_route: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn theme() {
+        let level = format!("{}\n[theme]\nwalls bunker\n", TERRAIN);
+        let metadata = LevelMetadata::parse(&level).expect("should parse");
+        assert_eq!(metadata.wall_theme, Some("bunker".to_string()));
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        let parse = |metadata: &str| LevelMetadata::parse(&format!("{}\n{}", TERRAIN, metadata));
+
+        assert!(parse("north loop 1,1 4,1").is_err(), "outside of section");
+        assert!(
+            parse("[paths]\nnorth loop 1,1 4,1").is_err(),
+            "unknown section"
+        );
+        assert!(
+            parse("[routes]\nnorth circle 1,1 4,1").is_err(),
+            "unknown mode"
+        );
+        assert!(
+            parse("[routes]\nnorth loop 1,1").is_err(),
+            "single waypoint"
+        );
+        assert!(
+            parse("[routes]\nnorth loop 1,1 4;1").is_err(),
+            "invalid waypoint"
+        );
+        assert!(
+            parse("[routes]\nnorth loop 1,1 4,1\nnorth loop 1,1 4,1").is_err(),
+            "duplicate"
+        );
+        assert!(parse("[spawns]\ngun-tower").is_err(), "spawn without tile");
+        assert!(
+            parse("[spawns]\ngun-tower 1,1 north south").is_err(),
+            "spawn with two routes"
+        );
+        assert!(parse("[theme]\nfloor marble").is_err(), "unknown theme");
+        assert!(parse("[theme]\nwalls").is_err(), "theme without name");
+    }
+}
@@ -1,8 +1,16 @@
 mod arena;
+mod autotile;
 mod builtins;
+mod geometry;
 pub mod levels;
+mod metadata;
 mod tilemap;
 mod tilepath;
+mod wall_theme;
 
-pub(crate) use arena::*;
-pub(crate) use tilepath::*;
+pub use arena::*;
+pub use autotile::*;
+pub use geometry::*;
+pub use metadata::*;
+pub use tilepath::*;
+pub use wall_theme::*;
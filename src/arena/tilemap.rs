@@ -54,7 +54,11 @@ pub struct Tilemap {
 fn get_bounds(row: &str) -> (usize, usize) {
     let start = row.find('=').unwrap_or(0);
     let end = row.rfind('=').unwrap_or(start);
-    let end = if end == start { row.len() - 1 } else { end };
+    let end = if end == start {
+        row.len().saturating_sub(1)
+    } else {
+        end
+    };
     (start, end)
 }
 
@@ -109,6 +113,9 @@ impl Tilemap {
             .collect();
 
         let nrows = lines.len();
+        if nrows == 0 {
+            return Err("terrain has no rows".into());
+        }
         let ncols = lines.iter().fold(0, |acc, s| max(acc, s.len()));
         let ntiles = nrows * ncols;
 
@@ -122,8 +129,10 @@ impl Tilemap {
             let mut col: usize = start;
             for c in line.chars().into_iter().skip(start).take(end - start + 1) {
                 let idx = tile_row * ncols + col;
-                let tile = tile_from_char(c)?;
-                tiles[idx] = tile;
+                let tile = tiles
+                    .get_mut(idx)
+                    .ok_or_else(|| format!("row {} of the terrain is out of bounds", row))?;
+                *tile = tile_from_char(c)?;
                 col = col + 1;
             }
         }
@@ -39,6 +39,20 @@ impl Tilepath {
         self.valid_tiles[col as usize][row as usize]
     }
 
+    /// If the tile containing the point given in tile units is valid.
+    /// Points outside of the grid never are.
+    pub fn is_valid_at(&self, x: f32, y: f32) -> bool {
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+        let (col, row) = (x as usize, y as usize);
+        self.valid_tiles
+            .get(col)
+            .and_then(|rows| rows.get(row))
+            .cloned()
+            .unwrap_or(false)
+    }
+
     pub fn empty_grid(ncols: usize, nrows: usize) -> Vec<Vec<bool>> {
         let mut grid: Vec<Vec<bool>> = Vec::with_capacity(ncols);
         grid.resize(ncols, Tilepath::empty_col(nrows));
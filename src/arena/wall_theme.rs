@@ -0,0 +1,136 @@
+use std::{collections::HashMap, error::Error, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::arena::WallKind;
+
+/// Theme used by levels that don't pick one in their `[theme]` section
+pub const DEFAULT_WALL_THEME: &str = "metal";
+
+/// How walls of one [WallKind] are rendered.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct WallVariant {
+    /// Texture inside the assets folder, repeated once per tile
+    pub texture: String,
+    /// Height of the wall relative to the tile size
+    pub height: f32,
+    /// Width of pillars and angled walls relative to the tile size, other walls fill their tile
+    pub thickness: f32,
+}
+
+impl Default for WallVariant {
+    fn default() -> Self {
+        Self {
+            texture: "textures/bg/wall-metal.png".to_string(),
+            height: 0.95,
+            thickness: 1.0,
+        }
+    }
+}
+
+/// Wall variants of a level theme, kinds without a variant use the `default`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WallTheme {
+    pub default: WallVariant,
+    pub variants: HashMap<WallKind, WallVariant>,
+}
+
+impl WallTheme {
+    pub fn variant(&self, kind: WallKind) -> &WallVariant {
+        self.variants.get(&kind).unwrap_or(&self.default)
+    }
+
+    /// All textures used by the theme, without duplicates.
+    pub fn textures(&self) -> Vec<&str> {
+        let mut textures: Vec<&str> = self
+            .variants
+            .values()
+            .chain(Some(&self.default))
+            .map(|variant| variant.texture.as_str())
+            .collect();
+        textures.sort_unstable();
+        textures.dedup();
+        textures
+    }
+}
+
+/// Wall themes by name, defined in `assets/themes/walls.ron`.
+pub struct WallThemes {
+    themes: HashMap<String, WallTheme>,
+}
+
+impl WallThemes {
+    pub fn from_ron(ron: &str) -> Result<WallThemes, Box<dyn Error>> {
+        let themes: HashMap<String, WallTheme> = ron::de::from_str(ron)?;
+        for (name, theme) in &themes {
+            let variants = theme.variants.values().chain(Some(&theme.default));
+            for variant in variants {
+                if variant.height <= 0.0 {
+                    return Err(format!("wall theme '{}' has walls without height", name).into());
+                }
+                if variant.thickness <= 0.0 || variant.thickness > 1.0 {
+                    return Err(format!(
+                        "wall theme '{}' needs thickness greater than 0 and at most 1",
+                        name
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(WallThemes { themes })
+    }
+
+    pub fn load(path: &Path) -> Result<WallThemes, Box<dyn Error>> {
+        let ron = fs::read_to_string(path)
+            .map_err(|err| format!("unable to read '{}': {}", path.display(), err))?;
+        WallThemes::from_ron(&ron)
+            .map_err(|err| format!("invalid wall themes '{}': {}", path.display(), err).into())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WallTheme> {
+        self.themes.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Arena;
+
+    #[test]
+    fn parse_themes() {
+        let themes = WallThemes::from_ron(
+            r#"{
+                "bunker": (
+                    default: (texture: "concrete.png", height: 0.6),
+                    variants: { Pillar: (texture: "post.png", thickness: 0.5) },
+                ),
+            }"#,
+        )
+        .expect("should parse");
+
+        let bunker = themes.get("bunker").expect("has bunker");
+        assert_eq!(bunker.variant(WallKind::Straight).height, 0.6);
+        assert_eq!(bunker.variant(WallKind::Straight).thickness, 1.0);
+        let pillar = bunker.variant(WallKind::Pillar);
+        assert_eq!(pillar.texture, "post.png");
+        assert_eq!(pillar.height, 0.95, "variants don't inherit from default");
+        assert_eq!(bunker.textures(), vec!["concrete.png", "post.png"]);
+        assert!(themes.get("metal").is_none());
+
+        assert!(WallThemes::from_ron(r#"{ "flat": (default: (height: 0.0)) }"#).is_err());
+        assert!(WallThemes::from_ron(r#"{ "wide": (default: (thickness: 1.5)) }"#).is_err());
+        assert!(WallThemes::from_ron(r#"{ "odd": (variants: { Arch: (height: 1.0) }) }"#).is_err());
+    }
+
+    #[test]
+    fn builtin_themes() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/themes/walls.ron");
+        let themes = WallThemes::load(&path).expect("should load");
+        assert!(themes.get(DEFAULT_WALL_THEME).is_some());
+        let coliseum = Arena::for_level("coliseum", 1).expect("should create arena");
+        assert!(themes.get(&coliseum.wall_theme).is_some());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{error::Error, path::PathBuf};
+
+use crate::ecs::resources::DisplayMode;
+
+pub const USAGE: &str = "\
+USAGE:
+    batufo [OPTIONS]
+
+OPTIONS:
+    --level <name|path>    Plays a builtin level or the level file at path
+    --seed <number>        Seeds the random numbers of the game, 0 by default
+    --record <file>        Records the input while playing the level to file
+    --replay <file>        Replays a recording with its level and seed, then quits
+    --mode <mode>          Display mode: windowed, borderless or fullscreen
+    --windowed             Same as --mode windowed
+    --fullscreen           Same as --mode fullscreen
+    --headless             Runs the simulation without window or renderer
+    --ticks <number>       Stops a headless run after the given number of ticks
+    --list-levels          Prints the names of the builtin levels
+    --help                 Prints this help";
+
+/// Options passed on the command line, which override the settings file.
+#[derive(Debug, Default, PartialEq)]
+pub struct Cli {
+    pub level: Option<String>,
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub mode: Option<DisplayMode>,
+    pub headless: bool,
+    pub ticks: Option<u64>,
+    pub list_levels: bool,
+    pub help: bool,
+}
+
+impl Cli {
+    /// Parses the arguments without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Cli, Box<dyn Error>> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for '{}'", arg))
+            };
+            match arg.as_str() {
+                "--level" => cli.level = Some(value()?),
+                "--seed" => cli.seed = Some(parse_number(&arg, &value()?)?),
+                "--record" => cli.record = Some(PathBuf::from(value()?)),
+                "--replay" => cli.replay = Some(PathBuf::from(value()?)),
+                "--mode" => cli.set_mode(parse_mode(&value()?)?)?,
+                "--windowed" => cli.set_mode(DisplayMode::Windowed)?,
+                "--fullscreen" => cli.set_mode(DisplayMode::Fullscreen)?,
+                "--headless" => cli.headless = true,
+                "--ticks" => cli.ticks = Some(parse_number(&arg, &value()?)?),
+                "--list-levels" => cli.list_levels = true,
+                "--help" | "-h" => cli.help = true,
+                _ => return Err(format!("unknown argument '{}'", arg).into()),
+            }
+        }
+        if cli.ticks.is_some() && !cli.headless {
+            return Err("'--ticks' is only supported with '--headless'".into());
+        }
+        if cli.replay.is_some() && (cli.level.is_some() || cli.seed.is_some()) {
+            return Err("'--replay' plays the level and seed it was recorded with".into());
+        }
+        if cli.replay.is_some() && cli.record.is_some() {
+            return Err("can't record while replaying".into());
+        }
+        if cli.headless && cli.mode.is_some() {
+            return Err("headless runs don't have a display mode".into());
+        }
+        Ok(cli)
+    }
+
+    fn set_mode(&mut self, mode: DisplayMode) -> Result<(), Box<dyn Error>> {
+        if matches!(self.mode, Some(current) if current != mode) {
+            return Err("conflicting display modes".into());
+        }
+        self.mode = Some(mode);
+        Ok(())
+    }
+}
+
+fn parse_number(arg: &str, value: &str) -> Result<u64, Box<dyn Error>> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' needs a positive number, got '{}'", arg, value).into())
+}
+
+fn parse_mode(value: &str) -> Result<DisplayMode, Box<dyn Error>> {
+    match value {
+        "windowed" => Ok(DisplayMode::Windowed),
+        "borderless" => Ok(DisplayMode::BorderlessFullscreen),
+        "fullscreen" => Ok(DisplayMode::Fullscreen),
+        _ => Err(format!("unknown display mode '{}'", value).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Cli, Box<dyn Error>> {
+        Cli::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(parse("").expect("should parse"), Cli::default());
+
+        let cli = parse("--level coliseum --fullscreen").expect("should parse");
+        assert_eq!(cli.level, Some("coliseum".to_string()));
+        assert_eq!(cli.mode, Some(DisplayMode::Fullscreen));
+
+        let cli = parse("--headless --ticks 600 --level levels/test.txt").expect("should parse");
+        assert!(cli.headless);
+        assert_eq!(cli.ticks, Some(600));
+        assert_eq!(cli.level, Some("levels/test.txt".to_string()));
+
+        let cli = parse("--level mini --seed 42 --record run.txt").expect("should parse");
+        assert_eq!(cli.seed, Some(42));
+        assert_eq!(cli.record, Some(PathBuf::from("run.txt")));
+
+        let cli = parse("--headless --replay run.txt").expect("should parse");
+        assert_eq!(cli.replay, Some(PathBuf::from("run.txt")));
+
+        let cli = parse("--mode borderless --list-levels").expect("should parse");
+        assert_eq!(cli.mode, Some(DisplayMode::BorderlessFullscreen));
+        assert!(cli.list_levels);
+
+        let cli = parse("--windowed --mode windowed").expect("should parse");
+        assert_eq!(cli.mode, Some(DisplayMode::Windowed));
+    }
+
+    #[test]
+    fn invalid_options() {
+        let invalid = [
+            ("--level", "missing level"),
+            ("--headless --ticks -1", "negative ticks"),
+            ("--mode maximized", "unknown mode"),
+            ("--windowed --fullscreen", "conflicting modes"),
+            ("--ticks 10", "ticks without headless"),
+            ("--headless --fullscreen", "headless with display mode"),
+            ("--seed lucky", "seed isn't a number"),
+            ("--replay", "missing recording"),
+            ("--replay run.txt --level mini", "replay with level"),
+            ("--replay run.txt --seed 1", "replay with seed"),
+            ("--replay a.txt --record b.txt", "record while replaying"),
+            ("--speed 2", "unknown argument"),
+        ];
+        for (args, reason) in invalid.iter() {
+            assert!(parse(args).is_err(), "{}", reason);
+        }
+    }
+}
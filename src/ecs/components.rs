@@ -1,9 +1,23 @@
 use bevy::math::Vec3;
 
-use crate::engine::TilePosition;
-
+mod behavior;
+mod explosive;
+mod knockback;
+mod lives;
 mod orthogonal_mover;
+mod patrol;
+mod perception;
+mod projectile_spawner;
+mod weapon;
+pub use behavior::*;
+pub use explosive::*;
+pub use knockback::*;
+pub use lives::*;
 pub use orthogonal_mover::*;
+pub use patrol::*;
+pub use perception::*;
+pub use projectile_spawner::*;
+pub use weapon::*;
 
 #[derive(Default)]
 pub struct Hero;
@@ -11,46 +25,45 @@ pub struct Hero;
 #[derive(Default)]
 pub struct HeroHull;
 
+/// Entity belonging to the current level, despawned when another level is loaded.
+#[derive(Default)]
+pub struct LevelEntity;
+
 #[derive(Default)]
 pub struct Velocity(pub Vec3);
 
 #[derive(Default)]
 pub struct HeadLights(pub bool);
 
-pub struct FloorTile(pub TilePosition);
+/// Side an entity fights on, projectiles only damage entities of the other faction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Faction {
+    Hero,
+    Enemy,
+}
 
 pub struct Projectile {
-    pub origin: Vec3,
+    pub faction: Faction,
     /// Squared range of projectile for world position, taking tile_size into account.
     pub range: f32,
+    /// Distance covered so far including bounces, in world units
+    pub distance_traveled: f32,
+    /// How many more times the projectile ricochets off walls instead of being destroyed
+    pub bounces_left: u8,
 
     /// Damage to deal out to life cycle components on collision
     pub health_damage: u16,
 }
 
-pub struct ProjectileSpawner {
-    /// Squared range of spawned projectiles for normalized Tilemap (tile_size: 1)
-    pub range: f32,
-    pub ticks_to_reload: u16,
-    pub ticks_until_reloaded: u16,
-    pub health_damage: u16,
-}
-
-impl Default for ProjectileSpawner {
-    fn default() -> Self {
-        Self {
-            range: 10.0,
-            ticks_to_reload: 60 * 5,
-            ticks_until_reloaded: 0,
-            health_damage: 1,
-        }
-    }
+/// Makes a projectile steer towards the closest target of the opposing faction.
+pub struct Homing {
+    /// Max turn rate in radians per second
+    pub turn_rate: f32,
 }
 
-impl ProjectileSpawner {
-    pub fn is_ready(&self) -> bool {
-        self.ticks_until_reloaded == 0
-    }
+/// Short lived effect like a laser beam or an explosion flash, removed once its time is up.
+pub struct Fading {
+    pub seconds_left: f32,
 }
 
 pub struct HeroFollower;
@@ -58,23 +71,36 @@ pub struct HeroShooter;
 
 pub struct LifeCycle {
     health: u16,
+    max_health: u16,
 }
 impl Default for LifeCycle {
     fn default() -> Self {
-        Self { health: 100 }
+        LifeCycle::new(100)
     }
 }
 impl LifeCycle {
+    pub fn new(max_health: u16) -> Self {
+        Self {
+            health: max_health,
+            max_health,
+        }
+    }
     pub fn health(&self) -> u16 {
         self.health
     }
+    pub fn health_ratio(&self) -> f32 {
+        self.health as f32 / self.max_health as f32
+    }
     pub fn is_alive(&self) -> bool {
         self.health > 0
     }
     pub fn deduct(&mut self, amount: u16) {
-        self.health -= amount;
+        self.health = self.health.saturating_sub(amount);
     }
     pub fn terminate(&mut self) {
         self.health = 0;
     }
+    pub fn revive(&mut self) {
+        self.health = self.max_health;
+    }
 }
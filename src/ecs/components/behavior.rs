@@ -0,0 +1,280 @@
+/// Possible state changes:
+///   Patrol -> Chase | Attack | Retreat
+///   Chase -> Investigate | Attack | Retreat
+///   Attack -> Patrol | Investigate | Chase | Retreat
+///   Investigate -> Patrol | Chase | Attack | Retreat
+///   Retreat -> Patrol
+///   Stationary enemies never Chase, Investigate or Retreat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BehaviorState {
+    /// Hero unknown, going about its business
+    Patrol,
+    /// Hero spotted but out of range, closing in
+    Chase,
+    /// Hero in range, holding position to fire
+    Attack,
+    /// Hero out of sight, moving to where it was last seen
+    Investigate,
+    /// Badly damaged, moving away from the hero
+    Retreat,
+}
+
+/// What an enemy knows about the hero and itself at a given moment.
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub hero_visible: bool,
+    pub hero_in_range: bool,
+    /// True if the enemy arrived where the hero was last seen or has no idea where that was
+    pub investigation_done: bool,
+    /// Remaining health relative to max health, `0.0..=1.0`
+    pub health_ratio: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct BehaviorConfig {
+    /// If the enemy can move, otherwise it never chases or retreats
+    pub mobile: bool,
+    /// Health ratio at or below which the enemy retreats
+    pub retreat_health_ratio: f32,
+    /// Seconds after which an enemy that lost sight of the hero gives up and patrols again,
+    /// even if it didn't finish investigating
+    pub lose_sight_after: f32,
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            mobile: true,
+            retreat_health_ratio: 0.3,
+            lose_sight_after: 8.0,
+        }
+    }
+}
+
+pub struct Behavior {
+    pub config: BehaviorConfig,
+    pub state: BehaviorState,
+    /// Seconds since the hero was last visible
+    pub unseen_for: f32,
+}
+
+impl Default for Behavior {
+    fn default() -> Self {
+        Behavior::new(BehaviorConfig::default())
+    }
+}
+
+impl Behavior {
+    pub fn new(config: BehaviorConfig) -> Self {
+        Self {
+            config,
+            state: BehaviorState::Patrol,
+            unseen_for: 0.0,
+        }
+    }
+
+    pub fn stationary() -> Self {
+        Behavior::new(BehaviorConfig {
+            mobile: false,
+            ..Default::default()
+        })
+    }
+
+    pub fn can_shoot(&self) -> bool {
+        match self.state {
+            BehaviorState::Chase | BehaviorState::Attack => true,
+            BehaviorState::Patrol | BehaviorState::Investigate | BehaviorState::Retreat => false,
+        }
+    }
+
+    /// Advances the state machine by `dt` seconds given what the enemy currently observes and
+    /// returns the new state.
+    pub fn update(&mut self, observation: &Observation, dt: f32) -> BehaviorState {
+        if observation.hero_visible {
+            self.unseen_for = 0.0;
+        } else {
+            self.unseen_for += dt;
+        }
+        self.state = self.next_state(observation);
+        self.state
+    }
+
+    fn next_state(&self, observation: &Observation) -> BehaviorState {
+        use BehaviorState::*;
+
+        let BehaviorConfig {
+            mobile,
+            retreat_health_ratio,
+            lose_sight_after,
+        } = self.config;
+        let lost_sight = !observation.hero_visible && self.unseen_for >= lose_sight_after;
+
+        if mobile && observation.health_ratio <= retreat_health_ratio {
+            return if lost_sight { Patrol } else { Retreat };
+        }
+
+        let spotted = if !observation.hero_visible {
+            None
+        } else if observation.hero_in_range {
+            Some(Attack)
+        } else if mobile {
+            Some(Chase)
+        } else {
+            None
+        };
+
+        match self.state {
+            Patrol | Retreat => spotted.unwrap_or(Patrol),
+            Investigate => spotted.unwrap_or(if lost_sight || observation.investigation_done {
+                Patrol
+            } else {
+                Investigate
+            }),
+            Chase | Attack if observation.hero_visible => spotted.unwrap_or(Patrol),
+            Chase | Attack if mobile => Investigate,
+            // stationary enemies hold position for a bit in case the hero shows up again
+            Chase | Attack if lost_sight => Patrol,
+            Chase | Attack => Attack,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.5;
+
+    fn observe(hero_visible: bool, hero_in_range: bool, health_ratio: f32) -> Observation {
+        Observation {
+            hero_visible,
+            hero_in_range,
+            investigation_done: false,
+            health_ratio,
+        }
+    }
+
+    #[test]
+    fn mobile_patrol_chase_attack() {
+        let mut behavior = Behavior::default();
+        assert_eq!(behavior.state, BehaviorState::Patrol);
+
+        assert_eq!(
+            behavior.update(&observe(false, false, 1.0), DT),
+            BehaviorState::Patrol
+        );
+        assert_eq!(
+            behavior.update(&observe(true, false, 1.0), DT),
+            BehaviorState::Chase
+        );
+        assert_eq!(
+            behavior.update(&observe(true, true, 1.0), DT),
+            BehaviorState::Attack
+        );
+        assert_eq!(
+            behavior.update(&observe(true, false, 1.0), DT),
+            BehaviorState::Chase,
+            "chases hero moving out of range"
+        );
+    }
+
+    #[test]
+    fn mobile_investigates_after_losing_sight() {
+        let mut behavior = Behavior::default();
+        behavior.update(&observe(true, true, 1.0), DT);
+        assert_eq!(behavior.state, BehaviorState::Attack);
+
+        // 8 seconds until giving up
+        for _ in 0..15 {
+            assert_eq!(
+                behavior.update(&observe(false, false, 1.0), DT),
+                BehaviorState::Investigate
+            );
+        }
+        assert_eq!(
+            behavior.update(&observe(false, false, 1.0), DT),
+            BehaviorState::Patrol
+        );
+    }
+
+    #[test]
+    fn mobile_done_investigating() {
+        let mut behavior = Behavior::default();
+        behavior.update(&observe(true, false, 1.0), DT);
+        assert_eq!(
+            behavior.update(&observe(false, false, 1.0), DT),
+            BehaviorState::Investigate
+        );
+        assert_eq!(
+            behavior.update(&observe(true, true, 1.0), DT),
+            BehaviorState::Attack,
+            "spots hero again"
+        );
+        assert_eq!(
+            behavior.update(&observe(false, false, 1.0), DT),
+            BehaviorState::Investigate
+        );
+        let done = Observation {
+            investigation_done: true,
+            ..observe(false, false, 1.0)
+        };
+        assert_eq!(behavior.update(&done, DT), BehaviorState::Patrol);
+    }
+
+    #[test]
+    fn mobile_retreats_when_damaged() {
+        let mut behavior = Behavior::default();
+        behavior.update(&observe(true, true, 1.0), DT);
+        assert_eq!(
+            behavior.update(&observe(true, true, 0.3), DT),
+            BehaviorState::Retreat
+        );
+        assert_eq!(
+            behavior.update(&observe(false, false, 0.3), DT),
+            BehaviorState::Retreat,
+            "keeps retreating right after losing sight"
+        );
+        for _ in 0..15 {
+            behavior.update(&observe(false, false, 0.3), DT);
+        }
+        assert_eq!(behavior.state, BehaviorState::Patrol);
+        assert_eq!(
+            behavior.update(&observe(true, false, 0.3), DT),
+            BehaviorState::Retreat
+        );
+    }
+
+    #[test]
+    fn stationary_never_moves() {
+        let mut behavior = Behavior::stationary();
+        assert_eq!(
+            behavior.update(&observe(true, false, 1.0), DT),
+            BehaviorState::Patrol
+        );
+        assert_eq!(
+            behavior.update(&observe(true, true, 0.1), DT),
+            BehaviorState::Attack,
+            "stationary enemies fight to the end"
+        );
+        assert_eq!(
+            behavior.update(&observe(true, false, 0.1), DT),
+            BehaviorState::Patrol
+        );
+    }
+
+    #[test]
+    fn stationary_holds_position_after_losing_sight() {
+        let mut behavior = Behavior::stationary();
+        behavior.update(&observe(true, true, 1.0), DT);
+        for _ in 0..15 {
+            assert_eq!(
+                behavior.update(&observe(false, false, 1.0), DT),
+                BehaviorState::Attack
+            );
+        }
+        assert_eq!(
+            behavior.update(&observe(false, false, 1.0), DT),
+            BehaviorState::Patrol
+        );
+    }
+}
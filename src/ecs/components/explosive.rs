@@ -0,0 +1,83 @@
+use serde::Deserialize;
+
+/// Makes an entity explode once its life cycle ends, i.e. bombs, towers or missiles.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Explosive {
+    /// Distance in tiles up to which entities are affected
+    pub radius: f32,
+    /// Damage dealt at the center, falling off towards the edge of the radius
+    pub damage: u16,
+    /// Impulse at the center, falling off towards the edge of the radius
+    pub knockback: f32,
+}
+
+impl Explosive {
+    /// Small explosion of missiles impacting.
+    pub fn missile() -> Self {
+        Self {
+            radius: 1.5,
+            damage: 15,
+            knockback: 0.05,
+        }
+    }
+
+    /// Explosion of bombs dropped by the hero.
+    pub fn bomb() -> Self {
+        Self {
+            radius: 4.0,
+            damage: 60,
+            knockback: 0.15,
+        }
+    }
+
+    pub fn damage_at(&self, distance: f32) -> u16 {
+        (self.damage as f32 * falloff(distance, self.radius)).round() as u16
+    }
+
+    pub fn knockback_at(&self, distance: f32) -> f32 {
+        self.knockback * falloff(distance, self.radius)
+    }
+}
+
+/// Linear falloff from `1.0` at the center to `0.0` at the radius and beyond.
+pub fn falloff(distance: f32, radius: f32) -> f32 {
+    if radius <= 0.0 || distance >= radius {
+        0.0
+    } else {
+        1.0 - distance / radius
+    }
+}
+
+/// Burns down until the explosive it is attached to goes off.
+pub struct Fuse {
+    pub seconds_left: f32,
+}
+
+/// Bomb lying in the arena waiting to be picked up by the hero.
+pub struct BombPickup;
+
+/// Bombs carried by the hero.
+#[derive(Default)]
+pub struct BombBag {
+    pub bombs: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_falloff() {
+        let explosive = Explosive {
+            radius: 4.0,
+            damage: 100,
+            knockback: 1.0,
+        };
+        assert_eq!(explosive.damage_at(0.0), 100, "center");
+        assert_eq!(explosive.damage_at(1.0), 75);
+        assert_eq!(explosive.damage_at(3.0), 25);
+        assert_eq!(explosive.damage_at(4.0), 0, "edge");
+        assert_eq!(explosive.damage_at(10.0), 0, "outside");
+        assert_eq!(explosive.knockback_at(2.0), 0.5);
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::math::Vec3;
+
+/// Speed below which a knocked back entity comes to rest
+const REST_SPEED: f32 = 1e-4;
+/// Ticks per second that velocity and drag are given for
+const TICKS_PER_SECOND: f32 = 60.0;
+
+/// Resistance of an entity against impulses, entities without mass count as `1.0`.
+pub struct Mass(pub f32);
+
+impl Default for Mass {
+    fn default() -> Self {
+        Mass(1.0)
+    }
+}
+
+/// Velocity an entity was shoved with by impulses, decaying by drag over time.
+/// Entities without this component can't be pushed around.
+pub struct Knockback {
+    /// Distance moved per 1/60 second
+    pub velocity: Vec3,
+    /// Fraction of the velocity lost per 1/60 second
+    pub drag: f32,
+}
+
+impl Default for Knockback {
+    fn default() -> Self {
+        Self {
+            velocity: Vec3::zero(),
+            drag: 0.08,
+        }
+    }
+}
+
+impl Knockback {
+    pub fn is_moving(&self) -> bool {
+        self.velocity.length_squared() > 0.0
+    }
+
+    /// Adds the velocity change caused by the impulse on an entity of the given mass.
+    pub fn apply_impulse(&mut self, impulse: Vec3, mass: f32) {
+        if mass > 0.0 {
+            self.velocity += impulse / mass;
+        }
+    }
+
+    /// Distance moved during `dt` seconds at the current velocity.
+    pub fn displacement(&self, dt: f32) -> Vec3 {
+        self.velocity * dt * TICKS_PER_SECOND
+    }
+
+    /// Returns the distance to move during `dt` seconds and lets drag slow the entity down.
+    pub fn step(&mut self, dt: f32) -> Vec3 {
+        let ticks = dt * TICKS_PER_SECOND;
+        let displacement = self.displacement(dt);
+        self.velocity *= (1.0 - self.drag).powf(ticks);
+        if self.velocity.length() < REST_SPEED {
+            self.velocity = Vec3::zero();
+        }
+        displacement
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::math::round;
+
+    #[test]
+    fn impulse_respects_mass() {
+        let mut light = Knockback::default();
+        let mut heavy = Knockback::default();
+        let impulse = Vec3::new(1.0, 0.0, 0.0);
+        light.apply_impulse(impulse, 1.0);
+        heavy.apply_impulse(impulse, 4.0);
+        assert_eq!(light.velocity.x, 1.0);
+        assert_eq!(heavy.velocity.x, 0.25);
+    }
+
+    #[test]
+    fn drag_decays_velocity() {
+        let mut knockback = Knockback {
+            velocity: Vec3::zero(),
+            drag: 0.5,
+        };
+        assert!(!knockback.is_moving());
+        knockback.apply_impulse(Vec3::new(0.0, 0.0, 1.0), 1.0);
+        assert!(knockback.is_moving());
+        let dt = 1.0 / TICKS_PER_SECOND;
+        assert_eq!(round(knockback.step(dt).z, 2), 1.0);
+        assert_eq!(round(knockback.step(dt).z, 2), 0.5);
+        assert_eq!(round(knockback.step(dt).z, 2), 0.25);
+        assert_eq!(
+            round(knockback.step(2.0 * dt).z, 2),
+            0.25,
+            "two ticks at once"
+        );
+        assert_eq!(round(knockback.velocity.z, 3), 0.031);
+        for _ in 0..20 {
+            knockback.step(dt);
+        }
+        assert!(!knockback.is_moving(), "comes to rest");
+    }
+
+    #[test]
+    fn distance_independent_of_frame_rate() {
+        let distance_at = |hz: f32| {
+            let mut knockback = Knockback::default();
+            knockback.apply_impulse(Vec3::new(1.0, 0.0, 0.0), 1.0);
+            let mut distance = 0.0;
+            for _ in 0..(hz as usize * 5) {
+                distance += knockback.step(1.0 / hz).x;
+            }
+            distance
+        };
+        let expected = distance_at(60.0);
+        for &hz in &[30.0, 144.0, 240.0] {
+            let distance = distance_at(hz);
+            assert!(
+                (distance - expected).abs() / expected < 0.05,
+                "{} at {} Hz vs {} at 60 Hz",
+                distance,
+                hz,
+                expected
+            );
+        }
+    }
+}
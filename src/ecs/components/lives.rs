@@ -0,0 +1,95 @@
+use crate::engine::TilePosition;
+
+/// Lives the hero starts a level with
+pub const HERO_LIVES: u8 = 3;
+/// Seconds the wreck of the hero stays in the arena before it respawns
+pub const WRECK_DURATION: f32 = 2.0;
+/// Seconds the hero can't be damaged after respawning
+pub const INVULNERABLE_DURATION: f32 = 3.0;
+/// Seconds the hero is shown or hidden while blinking
+const BLINK_INTERVAL: f32 = 0.15;
+
+/// Lives the hero has left and the tile it respawns at after losing one.
+pub struct Lives {
+    pub remaining: u8,
+    pub checkpoint: TilePosition,
+}
+
+impl Lives {
+    pub fn new(remaining: u8, checkpoint: TilePosition) -> Self {
+        Self {
+            remaining,
+            checkpoint,
+        }
+    }
+
+    /// Loses a life and returns `true` if the hero can respawn.
+    pub fn lose(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining > 0
+    }
+}
+
+/// Hero that was destroyed and stays in the arena as a wreck until it respawns.
+pub struct Wreck {
+    pub seconds_left: f32,
+}
+
+impl Default for Wreck {
+    fn default() -> Self {
+        Self {
+            seconds_left: WRECK_DURATION,
+        }
+    }
+}
+
+/// Entity that can't be damaged until the time runs out, it blinks meanwhile.
+pub struct Invulnerable {
+    pub seconds_left: f32,
+}
+
+impl Default for Invulnerable {
+    fn default() -> Self {
+        Self {
+            seconds_left: INVULNERABLE_DURATION,
+        }
+    }
+}
+
+impl Invulnerable {
+    /// Advances by the elapsed seconds and returns `true` once invulnerability ran out.
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.seconds_left = (self.seconds_left - dt).max(0.0);
+        self.seconds_left == 0.0
+    }
+
+    pub fn is_shown(&self) -> bool {
+        (self.seconds_left / BLINK_INTERVAL) as u32 % 2 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn losing_lives() {
+        let mut lives = Lives::new(2, TilePosition::new(1, 1, 0.0, 0.0));
+        assert!(lives.lose(), "one life left");
+        assert!(!lives.lose(), "no lives left");
+        assert!(!lives.lose(), "stays out of lives");
+        assert_eq!(lives.remaining, 0);
+    }
+
+    #[test]
+    fn invulnerability() {
+        let mut invulnerable = Invulnerable::default();
+        assert!(invulnerable.is_shown());
+        assert!(!invulnerable.tick(BLINK_INTERVAL * 0.5));
+        assert!(!invulnerable.is_shown(), "blinks");
+        assert!(!invulnerable.tick(BLINK_INTERVAL));
+        assert!(invulnerable.is_shown());
+        assert!(invulnerable.tick(INVULNERABLE_DURATION));
+        assert!(invulnerable.tick(1.0), "stays expired");
+    }
+}
@@ -0,0 +1,87 @@
+use crate::arena::{PatrolMode, PatrolRoute};
+
+/// Walks an entity along the waypoints of a patrol route.
+pub struct Patrol {
+    pub route: PatrolRoute,
+    waypoint_idx: usize,
+    forward: bool,
+}
+
+impl Patrol {
+    pub fn new(route: PatrolRoute) -> Self {
+        assert!(!route.waypoints.is_empty(), "patrol route needs waypoints");
+        Self {
+            route,
+            waypoint_idx: 0,
+            forward: true,
+        }
+    }
+
+    /// Waypoint the entity is currently heading to.
+    pub fn target(&self) -> (u32, u32) {
+        self.route.waypoints[self.waypoint_idx]
+    }
+
+    /// Moves on to the waypoint following the current target.
+    pub fn advance(&mut self) {
+        let last = self.route.waypoints.len() - 1;
+        if last == 0 {
+            return;
+        }
+        match self.route.mode {
+            PatrolMode::Loop => self.waypoint_idx = (self.waypoint_idx + 1) % (last + 1),
+            PatrolMode::PingPong => {
+                if self.forward && self.waypoint_idx == last {
+                    self.forward = false;
+                } else if !self.forward && self.waypoint_idx == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    self.waypoint_idx += 1;
+                } else {
+                    self.waypoint_idx -= 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patrol(mode: PatrolMode) -> Patrol {
+        Patrol::new(PatrolRoute {
+            name: "route".to_string(),
+            mode,
+            waypoints: vec![(1, 1), (2, 2), (3, 3)],
+        })
+    }
+
+    fn targets(patrol: &mut Patrol, count: usize) -> Vec<(u32, u32)> {
+        let mut targets = vec![patrol.target()];
+        for _ in 1..count {
+            patrol.advance();
+            targets.push(patrol.target());
+        }
+        targets
+    }
+
+    #[test]
+    fn loop_route() {
+        let mut patrol = patrol(PatrolMode::Loop);
+        assert_eq!(
+            targets(&mut patrol, 5),
+            vec![(1, 1), (2, 2), (3, 3), (1, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn ping_pong_route() {
+        let mut patrol = patrol(PatrolMode::PingPong);
+        assert_eq!(
+            targets(&mut patrol, 7),
+            vec![(1, 1), (2, 2), (3, 3), (2, 2), (1, 1), (2, 2), (3, 3)]
+        );
+    }
+}
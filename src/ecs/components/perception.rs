@@ -0,0 +1,198 @@
+use std::f32::consts::PI;
+
+use crate::engine::TilePosition;
+
+/// Lets an enemy perceive the hero inside its vision cone, unless walls are in the way.
+///
+/// Angles are in radians, counter clockwise starting at the positive x-axis of the tilemap,
+/// distances in tiles.
+pub struct Perception {
+    /// How far the enemy can see, scaled depending on whether the target is lit up
+    pub view_distance: f32,
+    /// Factor by which the view distance grows when the target has its lights on
+    pub lit_target_factor: f32,
+    /// Factor by which the view distance shrinks when the target moves in the dark
+    pub dark_target_factor: f32,
+    /// Full opening angle of the vision cone
+    pub field_of_view: f32,
+    /// Direction the enemy is looking
+    pub facing: f32,
+    /// How fast the enemy turns to scan its surroundings while it sees nothing, per second
+    pub scan_speed: f32,
+    /// If the target was visible when the enemy last looked
+    pub can_see_target: bool,
+    /// Distance to the target when it was last seen
+    pub target_distance: f32,
+    /// Where the target was last seen, if the enemy still remembers
+    pub last_known_position: Option<TilePosition>,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            view_distance: 12.0,
+            lit_target_factor: 1.5,
+            dark_target_factor: 0.4,
+            field_of_view: f32::to_radians(120.0),
+            facing: 0.0,
+            scan_speed: 0.0,
+            can_see_target: false,
+            target_distance: 0.0,
+            last_known_position: None,
+        }
+    }
+}
+
+impl Perception {
+    /// How far the enemy can see the target, depending on whether it is lit up.
+    pub fn effective_view_distance(&self, target_lit: bool) -> f32 {
+        if target_lit {
+            self.view_distance * self.lit_target_factor
+        } else {
+            self.view_distance * self.dark_target_factor
+        }
+    }
+
+    /// If a target in the given direction and distance is inside the vision cone.
+    pub fn in_view(&self, direction: f32, distance: f32, target_lit: bool) -> bool {
+        distance <= self.effective_view_distance(target_lit)
+            && angle_difference(self.facing, direction).abs() <= self.field_of_view / 2.0
+    }
+
+    /// Records that the target is visible at the given position and turns towards it.
+    pub fn spot(&mut self, position: TilePosition, direction: f32, distance: f32) {
+        self.can_see_target = true;
+        self.target_distance = distance;
+        self.last_known_position = Some(position);
+        self.facing = direction;
+    }
+
+    /// Records that the target isn't visible and keeps scanning for `dt` seconds.
+    pub fn lose_sight(&mut self, dt: f32) {
+        self.can_see_target = false;
+        self.facing = normalize_angle(self.facing + self.scan_speed * dt);
+    }
+
+    /// Forgets where the target was last seen, i.e. once the enemy investigated it.
+    pub fn forget(&mut self) {
+        self.last_known_position = None;
+    }
+}
+
+/// Normalizes the angle into the range `0..2PI`.
+pub fn normalize_angle(angle: f32) -> f32 {
+    let angle = angle % (2.0 * PI);
+    if angle < 0.0 {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// Smallest signed angle to turn from `from` to `to`, in the range `-PI..=PI`.
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    let delta = normalize_angle(to - from);
+    if delta > PI {
+        delta - 2.0 * PI
+    } else {
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::math::round;
+
+    fn deg(degrees: f32) -> f32 {
+        degrees.to_radians()
+    }
+
+    #[test]
+    fn angle_differences() {
+        let diff = |from: f32, to: f32| round(angle_difference(deg(from), deg(to)).to_degrees(), 1);
+        assert_eq!(diff(0.0, 90.0), 90.0);
+        assert_eq!(diff(90.0, 0.0), -90.0);
+        assert_eq!(diff(350.0, 10.0), 20.0);
+        assert_eq!(diff(10.0, 350.0), -20.0);
+        assert_eq!(diff(-90.0, 270.0), 0.0);
+        assert_eq!(diff(0.0, 180.0), 180.0);
+    }
+
+    #[test]
+    fn vision_cone() {
+        let perception = Perception {
+            view_distance: 10.0,
+            lit_target_factor: 1.0,
+            field_of_view: deg(90.0),
+            facing: deg(0.0),
+            ..Default::default()
+        };
+        let lit = true;
+        assert!(perception.in_view(deg(0.0), 5.0, lit), "straight ahead");
+        assert!(perception.in_view(deg(44.0), 10.0, lit), "edge of cone");
+        assert!(
+            perception.in_view(deg(316.0), 5.0, lit),
+            "other edge of cone"
+        );
+        assert!(!perception.in_view(deg(46.0), 5.0, lit), "outside of cone");
+        assert!(!perception.in_view(deg(180.0), 1.0, lit), "behind");
+        assert!(!perception.in_view(deg(0.0), 10.5, lit), "too far");
+    }
+
+    #[test]
+    fn headlights_affect_view_distance() {
+        let perception = Perception {
+            view_distance: 10.0,
+            lit_target_factor: 1.5,
+            dark_target_factor: 0.4,
+            field_of_view: deg(90.0),
+            facing: deg(0.0),
+            ..Default::default()
+        };
+        assert_eq!(perception.effective_view_distance(true), 15.0);
+        assert_eq!(perception.effective_view_distance(false), 4.0);
+
+        assert!(
+            perception.in_view(deg(0.0), 12.0, true),
+            "lit target far away"
+        );
+        assert!(
+            !perception.in_view(deg(0.0), 12.0, false),
+            "dark target far away"
+        );
+        assert!(
+            perception.in_view(deg(0.0), 3.5, false),
+            "dark target close by"
+        );
+        assert!(
+            !perception.in_view(deg(90.0), 3.5, true),
+            "lights don't widen the vision cone"
+        );
+    }
+
+    #[test]
+    fn spot_and_lose_sight() {
+        let mut perception = Perception {
+            scan_speed: deg(10.0),
+            ..Default::default()
+        };
+        let position = TilePosition::new(3, 4, 0.5, 0.5);
+        perception.spot(position.clone(), deg(90.0), 4.0);
+        assert!(perception.can_see_target);
+        assert_eq!(perception.last_known_position, Some(position.clone()));
+        assert_eq!(perception.facing, deg(90.0), "turns towards target");
+
+        perception.lose_sight(2.0);
+        assert!(!perception.can_see_target);
+        assert_eq!(
+            perception.last_known_position,
+            Some(position),
+            "remembers last sighting"
+        );
+        assert_eq!(round(perception.facing.to_degrees(), 1), 110.0, "scans");
+
+        perception.forget();
+        assert_eq!(perception.last_known_position, None);
+    }
+}
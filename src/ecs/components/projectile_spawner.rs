@@ -0,0 +1,154 @@
+use super::WeaponKind;
+
+/// Fires projectiles in rounds from a magazine, waiting `cooldown` seconds between shots and
+/// `reload_duration` seconds to refill the magazine once it is empty.
+pub struct ProjectileSpawner {
+    pub weapon: WeaponKind,
+    /// Distance projectiles travel per tick for normalized Tilemap (tile_size: 1)
+    pub projectile_speed: f32,
+    /// How many times projectiles ricochet off walls
+    pub bounces: u8,
+    /// Squared range of spawned projectiles for normalized Tilemap (tile_size: 1)
+    pub range: f32,
+    pub health_damage: u16,
+    /// Seconds to wait between two shots
+    pub cooldown: f32,
+    /// Shots that can be fired before the weapon needs to reload
+    pub magazine_size: u16,
+    /// Seconds it takes to refill an empty magazine
+    pub reload_duration: f32,
+    /// Radians a shot randomly misses its aim by at most, to either side
+    pub aim_error: f32,
+    /// Shots fired from the current magazine
+    pub rounds_fired: u16,
+    /// Seconds left until the cooldown or reload completes
+    pub seconds_until_ready: f32,
+}
+
+impl Default for ProjectileSpawner {
+    fn default() -> Self {
+        Self {
+            weapon: WeaponKind::default(),
+            projectile_speed: 0.2,
+            bounces: 0,
+            range: 10.0,
+            health_damage: 1,
+            cooldown: 5.0,
+            magazine_size: 1,
+            reload_duration: 5.0,
+            aim_error: 0.0,
+            rounds_fired: 0,
+            seconds_until_ready: 0.0,
+        }
+    }
+}
+
+impl ProjectileSpawner {
+    pub fn is_ready(&self) -> bool {
+        !self.is_reloading() && self.seconds_until_ready <= 0.0
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.rounds_left() == 0
+    }
+
+    pub fn rounds_left(&self) -> u16 {
+        self.magazine_size.saturating_sub(self.rounds_fired)
+    }
+
+    /// Advances cooldown and reload by `dt` seconds.
+    pub fn tick(&mut self, dt: f32) {
+        self.seconds_until_ready = (self.seconds_until_ready - dt).max(0.0);
+        if self.seconds_until_ready <= 0.0 && self.is_reloading() {
+            self.rounds_fired = 0;
+        }
+    }
+
+    /// Records that a shot was fired, starting the cooldown or reload if the magazine is empty.
+    pub fn fire(&mut self) {
+        self.rounds_fired = (self.rounds_fired + 1).min(self.magazine_size);
+        self.seconds_until_ready = if self.is_reloading() {
+            self.reload_duration
+        } else {
+            self.cooldown
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawner() -> ProjectileSpawner {
+        ProjectileSpawner {
+            cooldown: 0.5,
+            magazine_size: 2,
+            reload_duration: 2.0,
+            ..Default::default()
+        }
+    }
+
+    /// Seconds until the spawner is ready again when ticking at the given rate.
+    fn seconds_until_ready(spawner: &mut ProjectileSpawner, hz: f32) -> f32 {
+        let dt = 1.0 / hz;
+        let mut elapsed = 0.0;
+        while !spawner.is_ready() {
+            spawner.tick(dt);
+            elapsed += dt;
+        }
+        elapsed
+    }
+
+    #[test]
+    fn only_reloads_when_fired() {
+        let mut spawner = spawner();
+        assert!(spawner.is_ready());
+        for _ in 0..100 {
+            spawner.tick(1.0 / 60.0);
+        }
+        assert!(spawner.is_ready(), "stays ready without firing");
+        assert_eq!(spawner.rounds_left(), 2);
+    }
+
+    #[test]
+    fn cooldown_and_reload() {
+        let mut spawner = spawner();
+        spawner.fire();
+        assert!(!spawner.is_ready());
+        assert!(!spawner.is_reloading());
+        assert_eq!(spawner.rounds_left(), 1);
+        spawner.tick(0.4);
+        assert!(!spawner.is_ready(), "cooling down");
+        spawner.tick(0.2);
+        assert!(spawner.is_ready(), "cooled down");
+
+        spawner.fire();
+        assert!(spawner.is_reloading());
+        spawner.tick(1.5);
+        assert!(!spawner.is_ready(), "reloading");
+        spawner.tick(0.6);
+        assert!(spawner.is_ready(), "reloaded");
+        assert_eq!(spawner.rounds_left(), 2, "magazine refilled");
+    }
+
+    #[test]
+    fn fire_rate_independent_of_frame_rate() {
+        for &hz in &[30.0, 60.0, 144.0, 240.0] {
+            let mut spawner = spawner();
+            spawner.fire();
+            let cooldown = seconds_until_ready(&mut spawner, hz);
+            assert!(
+                (cooldown - 0.5).abs() <= 1.0 / hz + 1e-4,
+                "cooldown at {} Hz",
+                hz
+            );
+            spawner.fire();
+            let reload = seconds_until_ready(&mut spawner, hz);
+            assert!(
+                (reload - 2.0).abs() <= 1.0 / hz + 1e-4,
+                "reload at {} Hz",
+                hz
+            );
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::math::Vec3;
+use serde::Deserialize;
+
+use super::angle_difference;
+
+/// Kinds of weapons towers and the hero can equip.
+///
+/// Angles are in degrees as they are authored in the archetype assets.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum WeaponKind {
+    /// One projectile per shot
+    Single,
+    /// `count` projectiles fanned out evenly over an `arc` centered on the aim
+    Spread { count: u8, arc: f32 },
+    /// `count` projectiles fired one after the other, `interval` seconds apart
+    Burst { count: u8, interval: f32 },
+    /// Hits the first target in the line of fire instantly
+    Laser,
+    /// Projectile steering towards the closest target, turning at most `turn_rate` per second
+    Homing { turn_rate: f32 },
+}
+
+impl Default for WeaponKind {
+    fn default() -> Self {
+        WeaponKind::Single
+    }
+}
+
+/// A single projectile or laser beam released when a weapon fires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiredShot {
+    /// Direction in radians, counter clockwise starting at the positive x-axis of the tilemap
+    pub direction: f32,
+    /// Seconds to wait after the weapon fired before releasing this shot
+    pub delay: f32,
+}
+
+impl WeaponKind {
+    pub fn is_hitscan(&self) -> bool {
+        *self == WeaponKind::Laser
+    }
+
+    /// Max turn rate of projectiles in radians per second if they home in on targets.
+    pub fn homing_turn_rate(&self) -> Option<f32> {
+        match self {
+            WeaponKind::Homing { turn_rate } => Some(turn_rate.to_radians()),
+            _ => None,
+        }
+    }
+
+    /// Shots released when firing the weapon in the given direction.
+    pub fn shots(&self, direction: f32) -> Vec<FiredShot> {
+        let shot = |direction: f32, delay: f32| FiredShot { direction, delay };
+        match *self {
+            WeaponKind::Single | WeaponKind::Laser | WeaponKind::Homing { .. } => {
+                vec![shot(direction, 0.0)]
+            }
+            WeaponKind::Spread { count, .. } if count <= 1 => vec![shot(direction, 0.0)],
+            WeaponKind::Spread { count, arc } => {
+                let arc = arc.to_radians();
+                let step = arc / (count - 1) as f32;
+                (0..count)
+                    .map(|idx| shot(direction - arc / 2.0 + step * idx as f32, 0.0))
+                    .collect()
+            }
+            WeaponKind::Burst { count, interval } => (0..count)
+                .map(|idx| shot(direction, idx as f32 * interval))
+                .collect(),
+        }
+    }
+}
+
+/// Turns the velocity of a homing projectile towards the target by at most `max_turn` radians,
+/// keeping its speed. Only the x-z plane is considered.
+pub fn steer_towards(velocity: Vec3, to_target: Vec3, max_turn: f32) -> Vec3 {
+    let speed = (velocity.x * velocity.x + velocity.z * velocity.z).sqrt();
+    if speed == 0.0 || (to_target.x == 0.0 && to_target.z == 0.0) {
+        return velocity;
+    }
+    let current = velocity.z.atan2(velocity.x);
+    let desired = to_target.z.atan2(to_target.x);
+    let turn = angle_difference(current, desired)
+        .max(-max_turn)
+        .min(max_turn);
+    let direction = current + turn;
+    Vec3::new(speed * direction.cos(), velocity.y, speed * direction.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::math::round;
+
+    fn degrees(shots: Vec<FiredShot>) -> Vec<(f32, f32)> {
+        shots
+            .into_iter()
+            .map(|shot| (round(shot.direction.to_degrees(), 1), round(shot.delay, 2)))
+            .collect()
+    }
+
+    #[test]
+    fn single_shots() {
+        let direction = 90_f32.to_radians();
+        assert_eq!(
+            degrees(WeaponKind::Single.shots(direction)),
+            vec![(90.0, 0.0)]
+        );
+        assert_eq!(
+            degrees(WeaponKind::Laser.shots(direction)),
+            vec![(90.0, 0.0)]
+        );
+        let homing = WeaponKind::Homing { turn_rate: 90.0 };
+        assert_eq!(degrees(homing.shots(direction)), vec![(90.0, 0.0)]);
+        assert_eq!(homing.homing_turn_rate(), Some(90_f32.to_radians()));
+        assert!(WeaponKind::Laser.is_hitscan());
+        assert!(!homing.is_hitscan());
+    }
+
+    #[test]
+    fn spread_shots() {
+        let spread = WeaponKind::Spread {
+            count: 3,
+            arc: 30.0,
+        };
+        assert_eq!(
+            degrees(spread.shots(90_f32.to_radians())),
+            vec![(75.0, 0.0), (90.0, 0.0), (105.0, 0.0)]
+        );
+        let spread = WeaponKind::Spread {
+            count: 1,
+            arc: 30.0,
+        };
+        assert_eq!(degrees(spread.shots(0.0)), vec![(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn burst_shots() {
+        let burst = WeaponKind::Burst {
+            count: 3,
+            interval: 0.1,
+        };
+        assert_eq!(
+            degrees(burst.shots(0.0)),
+            vec![(0.0, 0.0), (0.0, 0.1), (0.0, 0.2)]
+        );
+    }
+
+    #[test]
+    fn steering() {
+        let velocity = Vec3::new(1.0, 0.0, 0.0);
+        let steered = steer_towards(velocity, Vec3::new(0.0, 0.0, 5.0), 45_f32.to_radians());
+        assert_eq!(round(steered.x, 3), 0.707, "turns at most max_turn");
+        assert_eq!(round(steered.z, 3), 0.707);
+
+        let steered = steer_towards(velocity, Vec3::new(1.0, 0.0, 1.0), 90_f32.to_radians());
+        assert_eq!(round(steered.x, 3), 0.707, "turns straight to target");
+        assert_eq!(round(steered.z, 3), 0.707);
+
+        let steered = steer_towards(velocity, Vec3::new(0.0, 0.0, -5.0), 90_f32.to_radians());
+        assert_eq!(round(steered.x, 3), 0.0, "turns either way");
+        assert_eq!(round(steered.z, 3), -1.0);
+    }
+}
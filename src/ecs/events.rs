@@ -1,12 +1,24 @@
-use crate::engine::TilePosition;
+use bevy::{ecs::Entity, math::Vec3};
 
-#[derive(Debug)]
+use crate::{
+    ecs::components::{Explosive, Faction},
+    engine::TilePosition,
+};
+
+#[derive(Debug, Clone)]
 pub struct ProjectileRequestedEvent {
     pub origin: TilePosition,
     pub direction: f32,
     pub velocity: f32,
     pub range: f32,
     pub health_damage: u16,
+    pub faction: Faction,
+    /// Max turn rate in radians per second for projectiles homing in on their target
+    pub homing_turn_rate: Option<f32>,
+    /// Seconds to wait before spawning the projectile, i.e. for later shots of a burst
+    pub delay: f32,
+    /// How many times the projectile ricochets off walls
+    pub bounces: u8,
 }
 
 impl ProjectileRequestedEvent {
@@ -16,6 +28,7 @@ impl ProjectileRequestedEvent {
         velocity: f32,
         range: f32,
         health_damage: u16,
+        faction: Faction,
     ) -> Self {
         Self {
             origin,
@@ -23,6 +36,58 @@ impl ProjectileRequestedEvent {
             velocity,
             range,
             health_damage,
+            faction,
+            homing_turn_rate: None,
+            delay: 0.0,
+            bounces: 0,
         }
     }
 }
+
+/// Requests a shot which hits the first target in the line of fire instantly.
+#[derive(Debug)]
+pub struct HitscanRequestedEvent {
+    pub origin: TilePosition,
+    pub direction: f32,
+    /// Range in tiles
+    pub range: f32,
+    pub health_damage: u16,
+    pub faction: Faction,
+}
+
+/// Laser shot that was resolved, from where it was fired to where it stopped.
+#[derive(Debug)]
+pub struct LaserFiredEvent {
+    pub start: Vec3,
+    pub direction: f32,
+    /// Length of the beam in world units
+    pub length: f32,
+}
+
+/// Damages and pushes away everything around the center that isn't shielded by walls.
+#[derive(Debug)]
+pub struct ExplosionEvent {
+    pub center: Vec3,
+    pub explosive: Explosive,
+}
+
+/// Replaces the current level, also used to restart it.
+#[derive(Debug)]
+pub struct LoadLevelEvent {
+    /// Name of a builtin level or path of a level file
+    pub level: String,
+    /// Keeps the hero where it is if it is still on a floor tile, used to reload a level in place
+    pub keep_hero: bool,
+}
+
+#[derive(Debug)]
+pub struct BombDroppedEvent {
+    pub position: Vec3,
+}
+
+/// Shoves an entity, changing its velocity depending on its mass.
+#[derive(Debug)]
+pub struct ImpulseEvent {
+    pub entity: Entity,
+    pub impulse: Vec3,
+}
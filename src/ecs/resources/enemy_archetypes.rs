@@ -0,0 +1,316 @@
+use std::{collections::HashMap, error::Error, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    arena::Arena,
+    ecs::components::{
+        Behavior, BehaviorConfig, Explosive, LifeCycle, Mass, Perception, ProjectileSpawner,
+        WeaponKind,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MeshSettings {
+    /// Size of the box along x, y and z, in tiles
+    pub size: (f32, f32, f32),
+    pub color: (f32, f32, f32),
+    /// Height of the box center above the floor, in tiles
+    pub y_offset: f32,
+}
+
+impl Default for MeshSettings {
+    fn default() -> Self {
+        Self {
+            size: (1.0, 1.0, 1.0),
+            color: (0.9, 0.4, 0.2),
+            y_offset: 0.6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum MovementSettings {
+    Stationary,
+    /// Rolls from tile to tile, completing `step_factor` rolls per second
+    Rolling {
+        step_factor: f32,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WeaponSettings {
+    pub kind: WeaponKind,
+    /// Distance projectiles travel per tick, in tiles
+    pub projectile_speed: f32,
+    /// How many times projectiles ricochet off walls, towers plan bank shots if this isn't 0
+    pub bounces: u8,
+    /// Range of the projectiles, in tiles
+    pub range: f32,
+    pub health_damage: u16,
+    /// Seconds between two shots
+    pub cooldown: f32,
+    /// Shots fired before the weapon needs to reload
+    pub magazine_size: u16,
+    /// Seconds it takes to refill an empty magazine
+    pub reload_duration: f32,
+    /// Degrees a shot randomly misses its aim by at most, to either side
+    pub aim_error: f32,
+}
+
+impl Default for WeaponSettings {
+    fn default() -> Self {
+        let spawner = ProjectileSpawner::default();
+        Self {
+            kind: spawner.weapon,
+            projectile_speed: spawner.projectile_speed,
+            bounces: spawner.bounces,
+            range: spawner.range.sqrt(),
+            health_damage: spawner.health_damage,
+            cooldown: spawner.cooldown,
+            magazine_size: spawner.magazine_size,
+            reload_duration: spawner.reload_duration,
+            aim_error: spawner.aim_error.to_degrees(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BehaviorSettings {
+    pub retreat_health_ratio: f32,
+    pub lose_sight_after: f32,
+}
+
+impl Default for BehaviorSettings {
+    fn default() -> Self {
+        let config = BehaviorConfig::default();
+        Self {
+            retreat_health_ratio: config.retreat_health_ratio,
+            lose_sight_after: config.lose_sight_after,
+        }
+    }
+}
+
+/// Same as [Perception] settings, except that angles are in degrees.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PerceptionSettings {
+    pub view_distance: f32,
+    pub lit_target_factor: f32,
+    pub dark_target_factor: f32,
+    pub field_of_view: f32,
+    pub facing: f32,
+    pub scan_speed: f32,
+}
+
+impl Default for PerceptionSettings {
+    fn default() -> Self {
+        let perception = Perception::default();
+        Self {
+            view_distance: perception.view_distance,
+            lit_target_factor: perception.lit_target_factor,
+            dark_target_factor: perception.dark_target_factor,
+            field_of_view: perception.field_of_view.to_degrees(),
+            facing: perception.facing.to_degrees(),
+            scan_speed: perception.scan_speed.to_degrees(),
+        }
+    }
+}
+
+/// Everything needed to spawn an enemy, defined in `assets/archetypes/enemies.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyArchetype {
+    #[serde(default)]
+    pub mesh: MeshSettings,
+    pub health: u16,
+    /// How hard it is to push the enemy around, stationary enemies can't be pushed at all
+    #[serde(default = "default_mass")]
+    pub mass: f32,
+    pub movement: MovementSettings,
+    #[serde(default)]
+    pub weapon: Option<WeaponSettings>,
+    #[serde(default)]
+    pub behavior: BehaviorSettings,
+    #[serde(default)]
+    pub perception: PerceptionSettings,
+    /// Explosion set off when the enemy is destroyed, if any
+    #[serde(default)]
+    pub explodes: Option<Explosive>,
+}
+
+fn default_mass() -> f32 {
+    Mass::default().0
+}
+
+impl EnemyArchetype {
+    pub fn is_mobile(&self) -> bool {
+        match self.movement {
+            MovementSettings::Stationary => false,
+            MovementSettings::Rolling { .. } => true,
+        }
+    }
+
+    pub fn behavior(&self) -> Behavior {
+        Behavior::new(BehaviorConfig {
+            mobile: self.is_mobile(),
+            retreat_health_ratio: self.behavior.retreat_health_ratio,
+            lose_sight_after: self.behavior.lose_sight_after,
+        })
+    }
+
+    pub fn perception(&self) -> Perception {
+        let settings = &self.perception;
+        Perception {
+            view_distance: settings.view_distance,
+            lit_target_factor: settings.lit_target_factor,
+            dark_target_factor: settings.dark_target_factor,
+            field_of_view: settings.field_of_view.to_radians(),
+            facing: settings.facing.to_radians(),
+            scan_speed: settings.scan_speed.to_radians(),
+            ..Default::default()
+        }
+    }
+
+    pub fn life_cycle(&self) -> LifeCycle {
+        LifeCycle::new(self.health)
+    }
+
+    pub fn projectile_spawner(&self) -> Option<ProjectileSpawner> {
+        self.weapon.as_ref().map(|weapon| ProjectileSpawner {
+            weapon: weapon.kind.clone(),
+            projectile_speed: weapon.projectile_speed,
+            bounces: weapon.bounces,
+            range: weapon.range.powi(2),
+            health_damage: weapon.health_damage,
+            cooldown: weapon.cooldown,
+            magazine_size: weapon.magazine_size,
+            reload_duration: weapon.reload_duration,
+            aim_error: weapon.aim_error.to_radians(),
+            ..Default::default()
+        })
+    }
+}
+
+pub struct EnemyArchetypes {
+    archetypes: HashMap<String, EnemyArchetype>,
+}
+
+impl EnemyArchetypes {
+    pub fn from_ron(ron: &str) -> Result<EnemyArchetypes, Box<dyn Error>> {
+        let archetypes: HashMap<String, EnemyArchetype> = ron::de::from_str(ron)?;
+        for (name, archetype) in &archetypes {
+            if archetype.health == 0 {
+                return Err(format!("enemy archetype '{}' needs health", name).into());
+            }
+            if let Some(weapon) = &archetype.weapon {
+                if weapon.magazine_size == 0 {
+                    return Err(format!("enemy archetype '{}' needs a magazine_size", name).into());
+                }
+            }
+        }
+        Ok(EnemyArchetypes { archetypes })
+    }
+
+    pub fn load(path: &Path) -> Result<EnemyArchetypes, Box<dyn Error>> {
+        let ron = fs::read_to_string(path)
+            .map_err(|err| format!("unable to read '{}': {}", path.display(), err))?;
+        EnemyArchetypes::from_ron(&ron)
+            .map_err(|err| format!("invalid enemy archetypes '{}': {}", path.display(), err).into())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EnemyArchetype> {
+        self.archetypes.get(name)
+    }
+
+    /// Ensures that all enemies spawned in the arena refer to a known archetype.
+    pub fn validate_spawns(&self, arena: &Arena) -> Result<(), Box<dyn Error>> {
+        match arena
+            .enemy_spawns
+            .iter()
+            .find(|spawn| self.get(&spawn.archetype).is_none())
+        {
+            Some(spawn) => Err(format!("unknown enemy archetype '{}'", spawn.archetype).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_archetypes() {
+        let archetypes = EnemyArchetypes::from_ron(
+            r#"{
+                "roller": (
+                    health: 50,
+                    movement: Rolling(step_factor: 2.0),
+                    weapon: Some((
+                        kind: Spread(count: 3, arc: 30.0),
+                        range: 5.0,
+                        magazine_size: 3,
+                    )),
+                    perception: (field_of_view: 90.0),
+                ),
+                "turret": (health: 10, movement: Stationary),
+            }"#,
+        )
+        .expect("should parse");
+
+        let roller = archetypes.get("roller").expect("has roller");
+        assert!(roller.is_mobile());
+        assert_eq!(roller.life_cycle().health(), 50);
+        let spawner = roller.projectile_spawner().expect("has weapon");
+        assert_eq!(spawner.range, 25.0);
+        assert_eq!(spawner.health_damage, 1, "defaults damage");
+        assert_eq!(spawner.magazine_size, 3);
+        assert_eq!(spawner.rounds_left(), 3, "starts loaded");
+        assert_eq!(spawner.cooldown, 5.0, "defaults cooldown");
+        assert_eq!(
+            spawner.weapon,
+            WeaponKind::Spread {
+                count: 3,
+                arc: 30.0
+            }
+        );
+        assert_eq!(roller.perception().field_of_view, 90_f32.to_radians());
+        assert_eq!(roller.perception().view_distance, 12.0, "defaults view");
+
+        let turret = archetypes.get("turret").expect("has turret");
+        assert!(!turret.is_mobile());
+        assert!(!turret.behavior().config.mobile);
+        assert!(turret.projectile_spawner().is_none());
+
+        assert!(archetypes.get("tank").is_none());
+    }
+
+    #[test]
+    fn invalid_archetypes() {
+        assert!(EnemyArchetypes::from_ron(r#"{ "turret": (movement: Stationary) }"#).is_err());
+        assert!(
+            EnemyArchetypes::from_ron(r#"{ "turret": (health: 0, movement: Stationary) }"#)
+                .is_err()
+        );
+        assert!(
+            EnemyArchetypes::from_ron(r#"{ "turret": (health: 1, movement: Flying) }"#).is_err()
+        );
+        assert!(EnemyArchetypes::from_ron(
+            r#"{ "turret": (health: 1, movement: Stationary, weapon: Some((magazine_size: 0))) }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn builtin_archetypes() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/archetypes/enemies.ron");
+        let archetypes = EnemyArchetypes::load(&path).expect("should load");
+        let arena = Arena::for_level("face off", 1).expect("should create arena");
+        archetypes
+            .validate_spawns(&arena)
+            .expect("face off spawns are known");
+    }
+}
@@ -0,0 +1,63 @@
+/// Random numbers of the game, seeded with `--seed` so that a run with the same seed and
+/// the same input plays out the same.
+pub struct GameRng {
+    state: u64,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next number of the SplitMix64 sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in `min..max`, `min` if the range is empty.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        // the upper 24 bits fit into the mantissa of an f32
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        if max <= min {
+            return min;
+        }
+        min + (max - min) * unit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = GameRng::new(42);
+        let mut b = GameRng::new(42);
+        let mut c = GameRng::new(43);
+        let a: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let b: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        let c: Vec<u64> = (0..5).map(|_| c.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn range() {
+        let mut rng = GameRng::default();
+        for _ in 0..1000 {
+            let x = rng.range(-0.5, 2.0);
+            assert!(x >= -0.5 && x < 2.0, "{} out of range", x);
+        }
+        assert_eq!(rng.range(1.0, 1.0), 1.0, "empty range");
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{LineWriter, Write},
+    path::Path,
+};
+
+use bevy::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+
+/// Keys controlling the hero, the only ones that are recorded.
+const RECORDED_KEYS: [(KeyCode, &str); 11] = [
+    (KeyCode::W, "W"),
+    (KeyCode::A, "A"),
+    (KeyCode::D, "D"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::B, "B"),
+    (KeyCode::L, "L"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+];
+
+/// Keys that are recorded together with the names they are saved with.
+pub fn recorded_keys() -> impl Iterator<Item = (KeyCode, &'static str)> {
+    RECORDED_KEYS.iter().cloned()
+}
+
+/// Input controlling the hero during one tick of play.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+    /// Names of the keys held down
+    pub keys: Vec<String>,
+    /// If the left mouse button, which turns the hero, is held down
+    pub turning: bool,
+    /// Horizontal mouse motion
+    pub mouse_dx: f32,
+}
+
+/// First line of a recording, each following line holds the [InputFrame] of one tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub level: String,
+    pub seed: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InputRecording {
+    pub header: RecordingHeader,
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("unable to read recording '{}': {}", path.display(), err))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().ok_or("recording is empty")?;
+        let header: RecordingHeader = ron::de::from_str(header)
+            .map_err(|err| format!("invalid recording header: {}", err))?;
+        let frames = lines
+            .enumerate()
+            .map(|(tick, line)| {
+                ron::de::from_str(line)
+                    .map_err(|err| format!("invalid input of tick {}: {}", tick, err).into())
+            })
+            .collect::<Result<Vec<InputFrame>, Box<dyn Error>>>()?;
+        Ok(Self { header, frames })
+    }
+}
+
+/// Writes the input of one play of a level to a recording, one line per tick so that it is
+/// complete up to the last tick even if the game is killed. Records nothing unless created
+/// for a file.
+#[derive(Default)]
+pub struct InputRecorder {
+    writer: Option<LineWriter<File>>,
+    ticks: usize,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path, header: &RecordingHeader) -> Result<Self, Box<dyn Error>> {
+        let file = File::create(path)
+            .map_err(|err| format!("unable to create recording '{}': {}", path.display(), err))?;
+        let mut recorder = Self {
+            writer: Some(LineWriter::new(file)),
+            ticks: 0,
+        };
+        recorder.write_line(header)?;
+        Ok(recorder)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Ticks recorded so far.
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    pub fn record(&mut self, frame: &InputFrame) -> Result<(), Box<dyn Error>> {
+        if self.is_recording() {
+            self.write_line(frame)?;
+            self.ticks += 1;
+        }
+        Ok(())
+    }
+
+    /// Closes the recording, nothing is recorded afterwards.
+    pub fn stop(&mut self) {
+        self.writer = None;
+    }
+
+    fn write_line<T: Serialize>(&mut self, value: &T) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = &mut self.writer {
+            writeln!(writer, "{}", ron::ser::to_string(value)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hands out the frames of a recording one tick at a time. Replays nothing unless created
+/// for a recording.
+#[derive(Default)]
+pub struct InputReplay {
+    frames: Vec<InputFrame>,
+    next: usize,
+}
+
+impl InputReplay {
+    pub fn new(frames: Vec<InputFrame>) -> Self {
+        Self { frames, next: 0 }
+    }
+
+    /// Returns `true` until all frames were replayed.
+    pub fn is_replaying(&self) -> bool {
+        self.next < self.frames.len()
+    }
+
+    pub fn next_frame(&mut self) -> Option<&InputFrame> {
+        let frame = self.frames.get(self.next)?;
+        self.next += 1;
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_load() {
+        let path = std::env::temp_dir().join("batufo-input-recording.txt");
+        let header = RecordingHeader {
+            level: "face off".to_string(),
+            seed: 7,
+        };
+        let frames = vec![
+            InputFrame::default(),
+            InputFrame {
+                keys: vec!["W".to_string(), "Space".to_string()],
+                turning: true,
+                mouse_dx: -2.5,
+            },
+        ];
+
+        let mut recorder = InputRecorder::create(&path, &header).expect("should create");
+        assert!(recorder.is_recording());
+        for frame in &frames {
+            recorder.record(frame).expect("should record");
+        }
+        assert_eq!(recorder.ticks(), 2);
+        recorder.stop();
+        assert!(!recorder.is_recording());
+        recorder
+            .record(&InputFrame::default())
+            .expect("ignored once stopped");
+        assert_eq!(recorder.ticks(), 2);
+
+        let recording = InputRecording::load(&path).expect("should load");
+        assert_eq!(recording.header, header);
+        assert_eq!(recording.frames, frames);
+
+        let mut replay = InputReplay::new(recording.frames);
+        assert!(replay.is_replaying());
+        assert_eq!(replay.next_frame(), Some(&frames[0]));
+        assert_eq!(replay.next_frame(), Some(&frames[1]));
+        assert!(!replay.is_replaying());
+        assert_eq!(replay.next_frame(), None);
+
+        fs::remove_file(&path).expect("should remove recording");
+    }
+
+    #[test]
+    fn invalid_recordings() {
+        assert!(InputRecording::parse("").is_err(), "empty");
+        assert!(InputRecording::parse("(seed: 1)").is_err(), "missing level");
+        assert!(
+            InputRecording::parse("(level: \"mini\", seed: 1)\n(keys: 3)").is_err(),
+            "invalid frame"
+        );
+        assert!(!InputRecorder::default().is_recording());
+        assert!(!InputReplay::default().is_replaying());
+    }
+}
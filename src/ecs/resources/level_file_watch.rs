@@ -0,0 +1,64 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Seconds between checks whether the level file changed
+pub const LEVEL_FILE_POLL_INTERVAL: f32 = 0.5;
+
+/// Watches the file of a level loaded from disk by polling its modification time and size.
+#[derive(Default)]
+pub struct LevelFileWatch {
+    watched: Option<(PathBuf, SystemTime, u64)>,
+}
+
+impl LevelFileWatch {
+    /// Returns `true` if the file changed since the last poll of the same path.
+    /// Paths that aren't files, i.e. builtin levels, never change.
+    pub fn poll(&mut self, path: &Path) -> bool {
+        let (modified, len) =
+            match fs::metadata(path).and_then(|meta| Ok((meta.modified()?, meta.len()))) {
+                Ok(stamp) => stamp,
+                Err(_) => {
+                    self.watched = None;
+                    return false;
+                }
+            };
+        let changed = match &self.watched {
+            Some((watched_path, watched_modified, watched_len)) if watched_path == path => {
+                *watched_modified != modified || *watched_len != len
+            }
+            _ => false,
+        };
+        self.watched = Some((path.to_path_buf(), modified, len));
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polling() {
+        let path = std::env::temp_dir().join("batufo-level-file-watch.txt");
+        let other = std::env::temp_dir().join("batufo-level-file-watch-other.txt");
+        fs::write(&path, "=p=").expect("should write level");
+        fs::write(&other, "=p=").expect("should write level");
+
+        let mut watch = LevelFileWatch::default();
+        assert!(!watch.poll(&path), "first poll");
+        assert!(!watch.poll(&path), "unchanged");
+
+        fs::write(&path, "=p =").expect("should write level");
+        assert!(watch.poll(&path), "changed");
+        assert!(!watch.poll(&path), "unchanged after change");
+
+        assert!(!watch.poll(&other), "switched to another level");
+        assert!(!watch.poll(Path::new("face off")), "builtin level");
+
+        fs::remove_file(&path).expect("should remove level");
+        fs::remove_file(&other).expect("should remove level");
+    }
+}
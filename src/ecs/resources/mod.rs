@@ -1,9 +1,23 @@
+mod enemy_archetypes;
 mod entity_tile;
+mod game_rng;
+mod input_recording;
+mod level_file_watch;
 mod position_converter;
+mod projectile_pool;
+mod projectile_visuals;
+mod settings;
 mod sniper;
 mod tile_state;
 
+pub use enemy_archetypes::*;
 pub use entity_tile::*;
+pub use game_rng::*;
+pub use input_recording::*;
+pub use level_file_watch::*;
 pub use position_converter::*;
+pub use projectile_pool::*;
+pub use projectile_visuals::*;
+pub use settings::*;
 pub use sniper::*;
 pub use tile_state::*;
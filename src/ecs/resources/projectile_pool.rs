@@ -0,0 +1,78 @@
+use bevy::ecs::Entity;
+
+/// Max number of idle projectile entities kept around for reuse
+pub const PROJECTILE_POOL_CAPACITY: usize = 256;
+
+/// Idle projectile entities which are recycled instead of despawning and spawning new ones.
+/// A pool with a capacity of `0` disables pooling.
+pub struct ProjectilePool {
+    capacity: usize,
+    idle: Vec<Entity>,
+}
+
+impl Default for ProjectilePool {
+    fn default() -> Self {
+        Self::new(PROJECTILE_POOL_CAPACITY)
+    }
+}
+
+impl ProjectilePool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            idle: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Takes an idle entity to be reused for a new projectile.
+    pub fn take(&mut self) -> Option<Entity> {
+        self.idle.pop()
+    }
+
+    /// Returns the entity of a destroyed projectile to the pool.
+    /// Returns `false` if the pool is full and the entity needs to be despawned instead.
+    pub fn put(&mut self, entity: Entity) -> bool {
+        if self.idle.len() >= self.capacity {
+            return false;
+        }
+        self.idle.push(entity);
+        true
+    }
+
+    /// Forgets all idle entities after they were despawned together with their level.
+    pub fn clear(&mut self) {
+        self.idle.clear();
+    }
+
+    #[cfg(test)]
+    pub fn idle(&self) -> usize {
+        self.idle.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycling() {
+        let mut pool = ProjectilePool::new(2);
+        assert_eq!(pool.take(), None);
+
+        assert!(pool.put(Entity::new(1)));
+        assert!(pool.put(Entity::new(2)));
+        assert!(!pool.put(Entity::new(3)), "pool is full");
+        assert_eq!(pool.idle(), 2);
+
+        assert_eq!(pool.take(), Some(Entity::new(2)));
+        assert_eq!(pool.take(), Some(Entity::new(1)));
+        assert_eq!(pool.take(), None);
+
+        assert!(pool.put(Entity::new(1)));
+        pool.clear();
+        assert_eq!(pool.take(), None, "cleared");
+
+        let mut disabled = ProjectilePool::new(0);
+        assert!(!disabled.put(Entity::new(1)), "pooling disabled");
+    }
+}
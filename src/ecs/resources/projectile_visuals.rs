@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::ecs::components::Faction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProjectileKind {
+    Bullet(Faction),
+    Missile(Faction),
+    /// Laser beam one unit long, scaled along x to the length of the shot
+    Beam,
+}
+
+/// Meshes and materials of projectiles, created once and shared by all projectiles of a kind.
+pub struct ProjectileVisuals {
+    visuals: HashMap<ProjectileKind, (Handle<Mesh>, Handle<StandardMaterial>)>,
+}
+
+impl ProjectileVisuals {
+    pub fn new(
+        tile_size: f32,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Self {
+        let bullet = meshes.add(Mesh::from(shape::Box::new(
+            tile_size / 5.0,
+            tile_size / 5.0,
+            tile_size / 5.0,
+        )));
+        let missile = meshes.add(Mesh::from(shape::Box::new(
+            tile_size / 3.0,
+            tile_size / 6.0,
+            tile_size / 6.0,
+        )));
+        let beam = meshes.add(Mesh::from(shape::Box::new(
+            1.0,
+            tile_size / 20.0,
+            tile_size / 20.0,
+        )));
+        let hero = materials.add(Color::rgb(0.3, 0.9, 0.4).into());
+        let enemy = materials.add(Color::rgb(0.3, 0.4, 0.9).into());
+        let laser = materials.add(Color::rgb(1.0, 0.1, 0.1).into());
+
+        let mut visuals = HashMap::new();
+        visuals.insert(
+            ProjectileKind::Bullet(Faction::Hero),
+            (bullet.clone(), hero.clone()),
+        );
+        visuals.insert(
+            ProjectileKind::Bullet(Faction::Enemy),
+            (bullet, enemy.clone()),
+        );
+        visuals.insert(
+            ProjectileKind::Missile(Faction::Hero),
+            (missile.clone(), hero),
+        );
+        visuals.insert(ProjectileKind::Missile(Faction::Enemy), (missile, enemy));
+        visuals.insert(ProjectileKind::Beam, (beam, laser));
+        Self { visuals }
+    }
+
+    pub fn get(&self, kind: ProjectileKind) -> (Handle<Mesh>, Handle<StandardMaterial>) {
+        let (mesh, material) = &self.visuals[&kind];
+        (mesh.clone(), material.clone())
+    }
+}
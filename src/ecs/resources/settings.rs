@@ -0,0 +1,233 @@
+use std::{error::Error, fs, path::Path};
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowSettings {
+    pub mode: DisplayMode,
+    pub width: f32,
+    pub height: f32,
+    pub vsync: bool,
+    pub resizable: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            width: 640.0,
+            height: 480.0,
+            vsync: false,
+            resizable: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderSettings {
+    /// Size of a tile in world units
+    pub tile_size: u32,
+    /// Scale applied to the tile size when rendering
+    pub render_scale: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            tile_size: 1,
+            render_scale: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraSettings {
+    /// Distance the camera follows the hero at when the game starts
+    pub distance_to_hero: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Distance zoomed per scrolled line
+    pub zoom_speed: f32,
+    /// Distance the free-fly camera moves per second
+    pub fly_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            distance_to_hero: 5.0,
+            min_distance: 3.0,
+            max_distance: 40.0,
+            zoom_speed: 0.02,
+            fly_speed: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlSettings {
+    /// Radians the hero turns per pixel the mouse moves
+    pub mouse_sensitivity: f32,
+    /// Velocity the hero gains per tick while thrusting
+    pub acceleration: f32,
+    /// Portion of the acceleration used to stop drifting sideways when not strafing
+    pub strafe_stability: f32,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 0.01,
+            acceleration: 0.004,
+            strafe_stability: 0.3,
+        }
+    }
+}
+
+/// Settings read from `assets/settings.ron` at startup, missing values use the defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub render: RenderSettings,
+    /// Name of a builtin level or path of a level file played first
+    pub level: String,
+    pub camera: CameraSettings,
+    pub controls: ControlSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: WindowSettings::default(),
+            render: RenderSettings::default(),
+            level: "face off".to_string(),
+            camera: CameraSettings::default(),
+            controls: ControlSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_ron(ron: &str) -> Result<Settings, Box<dyn Error>> {
+        let settings: Settings = ron::de::from_str(ron)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn load(path: &Path) -> Result<Settings, Box<dyn Error>> {
+        let ron = fs::read_to_string(path)
+            .map_err(|err| format!("unable to read '{}': {}", path.display(), err))?;
+        Settings::from_ron(&ron)
+            .map_err(|err| format!("invalid settings '{}': {}", path.display(), err).into())
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let Settings {
+            window,
+            render,
+            level,
+            camera,
+            controls,
+        } = self;
+        if window.width <= 0.0 || window.height <= 0.0 {
+            return Err("window needs a width and height greater than 0".into());
+        }
+        if render.tile_size == 0 || render.render_scale <= 0.0 {
+            return Err("render needs a tile_size and render_scale greater than 0".into());
+        }
+        if level.is_empty() {
+            return Err("level needs a name or path".into());
+        }
+        if camera.min_distance <= 0.0
+            || camera.min_distance > camera.max_distance
+            || camera.distance_to_hero < camera.min_distance
+            || camera.distance_to_hero > camera.max_distance
+        {
+            return Err("camera needs 0 < min_distance <= distance_to_hero <= max_distance".into());
+        }
+        if camera.zoom_speed < 0.0 || camera.fly_speed < 0.0 {
+            return Err("camera zoom_speed and fly_speed can't be negative".into());
+        }
+        if controls.mouse_sensitivity <= 0.0 || controls.acceleration <= 0.0 {
+            return Err("controls need a mouse_sensitivity and acceleration greater than 0".into());
+        }
+        if !(0.0..=1.0).contains(&controls.strafe_stability) {
+            return Err("controls need a strafe_stability between 0 and 1".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_settings() {
+        assert_eq!(
+            Settings::from_ron("()").expect("should parse"),
+            Settings::default()
+        );
+
+        let settings = Settings::from_ron(
+            r#"(
+                window: (mode: Fullscreen, width: 1024.0, height: 768.0),
+                level: "coliseum",
+                camera: (distance_to_hero: 10.0),
+            )"#,
+        )
+        .expect("should parse");
+        assert_eq!(settings.window.mode, DisplayMode::Fullscreen);
+        assert_eq!(settings.window.width, 1024.0);
+        assert!(!settings.window.vsync);
+        assert_eq!(settings.level, "coliseum");
+        assert_eq!(settings.camera.distance_to_hero, 10.0);
+        assert_eq!(settings.camera.max_distance, 40.0);
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let invalid = [
+            ("(window: (width: 0.0))", "no width"),
+            ("(render: (tile_size: 0))", "no tile size"),
+            ("(render: (render_scale: -1.0))", "negative render scale"),
+            ("(level: \"\")", "no level"),
+            ("(camera: (distance_to_hero: 50.0))", "beyond max distance"),
+            ("(camera: (min_distance: 8.0))", "closer than min distance"),
+            ("(camera: (fly_speed: -1.0))", "negative fly speed"),
+            (
+                "(controls: (mouse_sensitivity: 0.0))",
+                "no mouse sensitivity",
+            ),
+            ("(controls: (strafe_stability: 1.5))", "too much stability"),
+            ("(window: (mode: Maximized))", "unknown display mode"),
+            ("(sound: (volume: 1.0))", "unknown section"),
+        ];
+        for (ron, reason) in invalid.iter() {
+            assert!(Settings::from_ron(ron).is_err(), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn builtin_settings() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/settings.ron");
+        let settings = Settings::load(&path).expect("should load");
+        assert_eq!(
+            settings,
+            Settings::default(),
+            "defaults match the settings file"
+        );
+    }
+}
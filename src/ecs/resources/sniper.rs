@@ -1,8 +1,8 @@
-use bevy::prelude::Transform;
+use bevy::{math::Vec3, prelude::Transform};
 use crisscross::TileRaycaster;
 
 use crate::{
-    ai::{find_shot, Shot},
+    ai::{cast_ray, find_bank_shot, find_shot, RayHit, Shot},
     arena::Tilepath,
     engine::TilePosition,
 };
@@ -38,4 +38,47 @@ impl Sniper {
             Some(_) => None,
         }
     }
+
+    /// Casts a laser from origin in the given direction, stopping at the first target or wall.
+    pub fn cast_laser(
+        &self,
+        tile_path: &Tilepath,
+        origin: &TilePosition,
+        direction: f32,
+        targets: &[TilePosition],
+    ) -> Option<RayHit> {
+        cast_ray(&self.tile_caster, tile_path, origin, direction, targets)
+    }
+
+    /// Finds a shot at the target that bounces off at most `max_bounces` walls.
+    pub fn find_bank_shot(
+        &self,
+        tile_path: &Tilepath,
+        origin: &Transform,
+        target: &TilePosition,
+        max_bounces: u8,
+        range: f32,
+    ) -> Option<(Shot, TilePosition)> {
+        let origin_tile = self.converter.tile_from_translation(&origin.translation)?;
+        let shot = find_bank_shot(
+            &self.tile_caster,
+            tile_path,
+            &origin_tile,
+            target,
+            max_bounces,
+            range,
+        )?;
+        Some((shot, origin_tile))
+    }
+
+    /// If nothing but floor is between the two positions.
+    pub fn has_line_of_sight(&self, tile_path: &Tilepath, from: &Vec3, to: &Vec3) -> bool {
+        let from = self.converter.tile_from_translation(from);
+        let to = self.converter.tile_from_translation(to);
+        match (from, to) {
+            (Some(from), Some(to)) if from.is_same_tile(&to) => true,
+            (Some(from), Some(to)) => find_shot(&self.tile_caster, tile_path, &from, &to).is_some(),
+            _ => false,
+        }
+    }
 }
@@ -1,4 +1,4 @@
 pub mod physics;
 mod position;
 
-pub(crate) use position::*;
+pub use position::*;
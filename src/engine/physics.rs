@@ -26,3 +26,59 @@ pub fn perp_vector_for_rotation_y(rotation: &Quat) -> Vec3 {
     let angle = perpendicular(angle_for_rotation_y(rotation));
     vector_for_angle_y(angle)
 }
+
+/// Reflects `velocity` if moving by it from `position` ends up inside a blocked tile.
+/// The wall face normal is derived from the tile edge that is crossed, hitting a corner reflects
+/// along both axes. Returns `None` if the way is clear.
+///
+/// Works in any plane, i.e. x-z in world space or x-y on the tilemap.
+pub fn reflect_off_walls(
+    (x, y): (f32, f32),
+    (vx, vy): (f32, f32),
+    is_blocked: impl Fn(f32, f32) -> bool,
+) -> Option<(f32, f32)> {
+    if !is_blocked(x + vx, y + vy) {
+        return None;
+    }
+    let crosses_x_edge = is_blocked(x + vx, y);
+    let crosses_y_edge = is_blocked(x, y + vy);
+    match (crosses_x_edge, crosses_y_edge) {
+        (true, false) => Some((-vx, vy)),
+        (false, true) => Some((vx, -vy)),
+        _ => Some((-vx, -vy)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wall along x = 2 and y = 2
+    fn is_blocked(x: f32, y: f32) -> bool {
+        x >= 2.0 || y >= 2.0
+    }
+
+    #[test]
+    fn reflections() {
+        assert_eq!(
+            reflect_off_walls((1.0, 1.0), (0.5, 0.5), is_blocked),
+            None,
+            "way is clear"
+        );
+        assert_eq!(
+            reflect_off_walls((1.8, 1.0), (0.4, 0.2), is_blocked),
+            Some((-0.4, 0.2)),
+            "crossing vertical edge"
+        );
+        assert_eq!(
+            reflect_off_walls((1.0, 1.8), (-0.4, 0.4), is_blocked),
+            Some((-0.4, -0.4)),
+            "crossing horizontal edge"
+        );
+        assert_eq!(
+            reflect_off_walls((1.9, 1.9), (0.2, 0.2), is_blocked),
+            Some((-0.2, -0.2)),
+            "hitting corner"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+pub mod ai;
+pub mod animations;
+pub mod arena;
+pub mod cli;
+pub mod ecs;
+pub mod engine;
+pub mod plugins;
+pub mod utils;
@@ -1,36 +1,60 @@
-use bevy::prelude::*;
-use plugins::{LifeCyclePlugin, ProjectilePlugin};
+use std::{env, process};
 
-use crate::plugins::{
-    ArenaPlugin, CameraPlugin, GamePlugin, GunTowerPlugin, LightPlugin, PlayerInputPlugin,
-    PlayerMovementPlugin, PlayerRenderPlugin,
+use batufo::{
+    arena::levels::Levels,
+    cli::{Cli, USAGE},
+    plugins::{
+        AppState, AppStatePlugin, BatufoPlugins, GamePlugin, HeadlessPlugin, SimulationPlugins,
+    },
 };
-
-mod ai;
-mod animations;
-mod arena;
-mod ecs;
-mod engine;
-mod plugins;
-mod utils;
+use bevy::{input::InputPlugin, log::LogPlugin, prelude::*};
 
 const BG: &str = "000000"; // "1E1C32"
 
 fn main() {
-    App::build()
-        .add_resource(ClearColor(
+    let cli = match Cli::parse(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    if cli.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if cli.list_levels {
+        for name in Levels::new().names() {
+            println!("{}", name);
+        }
+        return;
+    }
+    let game = GamePlugin {
+        level: cli.level,
+        mode: cli.mode,
+        seed: cli.seed,
+        record: cli.record,
+        replay: cli.replay,
+    };
+
+    let mut app = App::build();
+    if cli.headless {
+        app.add_plugin(game)
+            .add_plugin(HeadlessPlugin { ticks: cli.ticks })
+            .add_plugins(MinimalPlugins)
+            .add_plugin(LogPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(AppStatePlugin {
+                initial: AppState::Loading,
+            })
+            .add_plugins(SimulationPlugins);
+    } else {
+        app.add_resource(ClearColor(
             Color::hex(BG).expect("Invalid Background Color"),
         ))
-        .add_plugin(GamePlugin)
-        .add_plugin(LightPlugin)
-        .add_plugins(DefaultPlugins)
-        .add_plugin(CameraPlugin)
-        .add_plugin(ArenaPlugin)
-        .add_plugin(LifeCyclePlugin)
-        .add_plugin(PlayerRenderPlugin)
-        .add_plugin(PlayerInputPlugin)
-        .add_plugin(PlayerMovementPlugin)
-        .add_plugin(ProjectilePlugin)
-        .add_plugin(GunTowerPlugin)
-        .run();
+        .add_plugin(game)
+        .add_plugins(BatufoPlugins)
+        .add_plugins(DefaultPlugins);
+    }
+    app.run();
 }
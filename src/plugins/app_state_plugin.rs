@@ -0,0 +1,272 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    arena::{levels::Levels, Arena},
+    ecs::{
+        components::{Faction, LifeCycle},
+        events::LoadLevelEvent,
+    },
+};
+
+/// Stage running the systems scoped to the current [AppState], right after the update stage.
+pub const APP_STATE_STAGE: &str = "app_state";
+
+const MENU_FONT: &str = "fonts/DejaVuSansMono-Bold.ttf";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppState {
+    MainMenu,
+    /// Level entities are spawned when entering, play starts once they exist
+    Loading,
+    Playing,
+    Paused,
+    GameOver,
+    LevelComplete,
+}
+
+/// Queues a transition to `next`, unless another system already queued one this frame.
+/// The first transition queued in a frame wins, e.g. game over beats level complete.
+pub fn queue_state(state: &mut State<AppState>, next: AppState) {
+    state.set_next(next).ok();
+}
+
+/// Drives the [AppState] of the simulation, starting out in the given state.
+pub struct AppStatePlugin {
+    pub initial: AppState,
+}
+
+impl Default for AppStatePlugin {
+    fn default() -> Self {
+        Self {
+            initial: AppState::MainMenu,
+        }
+    }
+}
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(State::new(self.initial))
+            .add_stage_after(
+                stage::UPDATE,
+                APP_STATE_STAGE,
+                StateStage::<AppState>::default(),
+            )
+            .on_state_update(APP_STATE_STAGE, AppState::Loading, finish_loading.system())
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                detect_level_complete.system(),
+            );
+    }
+}
+
+/// Menus shown for each [AppState] and the keys to move between states.
+#[derive(Default)]
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup_ui_camera.system())
+            .on_state_enter(
+                APP_STATE_STAGE,
+                AppState::MainMenu,
+                enter_main_menu.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::MainMenu,
+                main_menu_input.system(),
+            )
+            .on_state_exit(APP_STATE_STAGE, AppState::MainMenu, despawn_menu.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, playing_input.system())
+            .on_state_enter(APP_STATE_STAGE, AppState::Paused, enter_paused.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Paused, paused_input.system())
+            .on_state_exit(APP_STATE_STAGE, AppState::Paused, despawn_menu.system())
+            .on_state_enter(
+                APP_STATE_STAGE,
+                AppState::GameOver,
+                enter_game_over.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::GameOver,
+                game_over_input.system(),
+            )
+            .on_state_exit(APP_STATE_STAGE, AppState::GameOver, despawn_menu.system())
+            .on_state_enter(
+                APP_STATE_STAGE,
+                AppState::LevelComplete,
+                enter_level_complete.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::LevelComplete,
+                level_complete_input.system(),
+            )
+            .on_state_exit(
+                APP_STATE_STAGE,
+                AppState::LevelComplete,
+                despawn_menu.system(),
+            );
+    }
+}
+
+/// Marks the text of the menu shown for the current state.
+struct MenuText;
+
+fn setup_ui_camera(commands: &mut Commands) {
+    commands.spawn(CameraUiBundle::default());
+}
+
+fn spawn_menu(commands: &mut Commands, asset_server: &AssetServer, message: &str) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                margin: Rect::all(Val::Auto),
+                ..Default::default()
+            },
+            text: Text {
+                value: message.to_string(),
+                font: asset_server.load(MENU_FONT),
+                style: TextStyle {
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        })
+        .with(MenuText);
+}
+
+fn despawn_menu(commands: &mut Commands, menu_query: Query<Entity, With<MenuText>>) {
+    for entity in menu_query.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+fn enter_main_menu(commands: &mut Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        "BATUFO  Enter to play, Esc to quit",
+    );
+}
+
+fn enter_paused(commands: &mut Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        "PAUSED  Esc to resume, R to restart, Q to quit",
+    );
+}
+
+fn enter_game_over(commands: &mut Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        "GAME OVER  Enter to restart, Esc to quit",
+    );
+}
+
+fn enter_level_complete(commands: &mut Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        "LEVEL COMPLETE  Enter for the next level, Esc to quit",
+    );
+}
+
+fn main_menu_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut state: ResMut<State<AppState>>,
+    mut exit_events: ResMut<Events<AppExit>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        queue_state(&mut state, AppState::Loading);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        exit_events.send(AppExit);
+    }
+}
+
+/// Systems entering [AppState::Loading] spawned the level, whose entities exist by now.
+fn finish_loading(mut state: ResMut<State<AppState>>) {
+    queue_state(&mut state, AppState::Playing);
+}
+
+fn playing_input(keyboard_input: Res<Input<KeyCode>>, mut state: ResMut<State<AppState>>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        queue_state(&mut state, AppState::Paused);
+    }
+}
+
+fn paused_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    arena: Res<Arena>,
+    mut state: ResMut<State<AppState>>,
+    mut load_level_events: ResMut<Events<LoadLevelEvent>>,
+    mut exit_events: ResMut<Events<AppExit>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        queue_state(&mut state, AppState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::R) {
+        load_level_events.send(LoadLevelEvent {
+            level: arena.level.clone(),
+            keep_hero: false,
+        });
+    } else if keyboard_input.just_pressed(KeyCode::Q) {
+        exit_events.send(AppExit);
+    }
+}
+
+fn game_over_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    arena: Res<Arena>,
+    mut load_level_events: ResMut<Events<LoadLevelEvent>>,
+    mut exit_events: ResMut<Events<AppExit>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        load_level_events.send(LoadLevelEvent {
+            level: arena.level.clone(),
+            keep_hero: false,
+        });
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        exit_events.send(AppExit);
+    }
+}
+
+fn level_complete_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    arena: Res<Arena>,
+    mut load_level_events: ResMut<Events<LoadLevelEvent>>,
+    mut exit_events: ResMut<Events<AppExit>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let level = Levels::new()
+            .next_level(&arena.level)
+            .map_or_else(|| arena.level.clone(), String::from);
+        load_level_events.send(LoadLevelEvent {
+            level,
+            keep_hero: false,
+        });
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        exit_events.send(AppExit);
+    }
+}
+
+fn detect_level_complete(
+    arena: Res<Arena>,
+    mut state: ResMut<State<AppState>>,
+    faction_query: Query<(&Faction, &LifeCycle)>,
+) {
+    if arena.enemy_spawns.is_empty() {
+        return;
+    }
+    let enemies_alive = faction_query
+        .iter()
+        .any(|(faction, life)| *faction == Faction::Enemy && life.is_alive());
+    if !enemies_alive {
+        queue_state(&mut state, AppState::LevelComplete);
+    }
+}
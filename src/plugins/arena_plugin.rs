@@ -1,74 +1,114 @@
-use bevy::{asset::AssetPath, prelude::*};
+use std::collections::HashMap;
 
-use crate::{arena::Arena, ecs::components::FloorTile};
+use bevy::{
+    asset::AssetPath,
+    prelude::*,
+    render::{
+        mesh::Indices,
+        pipeline::PrimitiveTopology,
+        texture::{AddressMode, Texture},
+    },
+};
 
-use super::game_plugin::{GameAssets, GameRender};
+use crate::{
+    arena::{arena_geometry, Arena, FloorAtlas, MeshData, WallThemes},
+    ecs::components::LevelEntity,
+};
+
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::{GameAssets, GameRender},
+};
 
 #[derive(Default)]
 pub struct ArenaPlugin;
 
 impl Plugin for ArenaPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(setup_floor.system())
-            .add_startup_system(setup_walls.system());
+        app.on_state_enter(APP_STATE_STAGE, AppState::Loading, setup_arena.system())
+            .add_system(repeat_wall_texture.system());
     }
 }
 
-fn setup_floor(
+/// Wall meshes use uvs in tiles, so wall textures need to repeat to be shown once per tile.
+struct WallTextures(Vec<Handle<Texture>>);
+
+fn to_mesh(data: MeshData) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, data.positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, data.normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, data.uvs);
+    mesh.set_indices(Some(Indices::U32(data.indices)));
+    mesh
+}
+
+fn setup_arena(
     commands: &mut Commands,
     game_assets: Res<GameAssets>,
     game_render: Res<GameRender>,
     arena: Res<Arena>,
+    wall_themes: Res<WallThemes>,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // TODO: this is actually a spritesheet and the floor tiles should map to once tile
-    // in it, instead of rendering the entire thing, however haven't found a way to get
-    // at individual textures of a texture atlas.
     let asset = &game_assets.floor_tiles;
-    let texture_handle = asset_server.load(AssetPath::new(asset.path.clone(), None));
-    let material = materials.add(texture_handle.into());
-    let size = game_render.tile_size as f32 * 0.92;
+    let floor_atlas = FloorAtlas::new(asset.rows as u32, asset.cols as u32)
+        .expect("FATAL: floor tiles do not match the floor atlas");
+    let floor_texture = asset_server.load(AssetPath::new(asset.path.clone(), None));
+    let floor_material = materials.add(floor_texture.into());
 
-    for (_idx, tile) in arena.floor_tiles.iter().enumerate() {
-        let pos = tile.to_world_position(game_render.tile_size);
-        let transform: Transform = pos.into();
-        commands
-            .spawn(PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Box::new(size, size / 10.0, size))),
-                material: material.clone(),
-                transform,
-                ..Default::default()
-            })
-            .with(FloorTile(tile.clone()));
+    let wall_theme = wall_themes
+        .get(&arena.wall_theme)
+        .expect("FATAL: unknown wall theme");
+    let mut wall_textures = Vec::new();
+    let mut wall_materials = HashMap::new();
+    for path in wall_theme.textures() {
+        let texture: Handle<Texture> = asset_server.load(path);
+        wall_materials.insert(path.to_string(), materials.add(texture.clone().into()));
+        wall_textures.push(texture);
+    }
+    commands.insert_resource(WallTextures(wall_textures));
+
+    let tile_size = game_render.tile_size as f32;
+    for chunk in arena_geometry(&arena, tile_size, &floor_atlas, wall_theme) {
+        let walls = chunk
+            .walls
+            .into_iter()
+            .map(|(texture, data)| (data, wall_materials[&texture].clone()));
+        for (data, material) in Some((chunk.floor, floor_material.clone()))
+            .into_iter()
+            .chain(walls)
+        {
+            if data.is_empty() {
+                continue;
+            }
+            commands
+                .spawn(PbrBundle {
+                    mesh: meshes.add(to_mesh(data)),
+                    material,
+                    ..Default::default()
+                })
+                .with(LevelEntity);
+        }
     }
 }
 
-fn setup_walls(
-    commands: &mut Commands,
-    game_assets: Res<GameAssets>,
-    game_render: Res<GameRender>,
-    arena: Res<Arena>,
-    asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+fn repeat_wall_texture(
+    wall_textures: Res<WallTextures>,
+    mut event_reader: Local<EventReader<AssetEvent<Texture>>>,
+    texture_events: Res<Events<AssetEvent<Texture>>>,
+    mut textures: ResMut<Assets<Texture>>,
 ) {
-    let asset = &game_assets.wall_metal;
-    let texture_handle = asset_server.load(AssetPath::new(asset.path.clone(), None));
-    let material = materials.add(texture_handle.into());
-
-    let size = game_render.tile_size as f32 * 0.95;
-
-    for tile in arena.walls.iter() {
-        let mut pos = tile.to_world_position(game_render.tile_size);
-        pos.y = size * 0.5;
-        let transform: Transform = pos.into();
-        commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(size, size, size))),
-            material: material.clone(),
-            transform,
-            ..Default::default()
-        });
+    for event in event_reader.iter(&texture_events) {
+        if let AssetEvent::Created { handle } = event {
+            if !wall_textures.0.contains(handle) {
+                continue;
+            }
+            if let Some(texture) = textures.get_mut(handle) {
+                texture.sampler.address_mode_u = AddressMode::Repeat;
+                texture.sampler.address_mode_v = AddressMode::Repeat;
+            }
+        }
     }
 }
@@ -1,21 +1,50 @@
 use crate::engine::physics::vector_for_rotation_y;
 use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
     render::camera::{Camera, PerspectiveProjection},
 };
 
-use crate::ecs::components::Hero;
+use crate::{
+    arena::Arena,
+    ecs::{
+        components::Hero,
+        resources::{CameraSettings, ControlSettings},
+    },
+};
+
+use super::game_plugin::{GameCameras, GameRender};
 
-use super::game_plugin::GameCameras;
-use bevy::input::mouse::MouseWheel;
+/// Extra room around the arena when showing all of it
+const OVERVIEW_MARGIN: f32 = 1.1;
 
 struct CameraProperties {
     distance_to_hero: f32,
 }
-impl Default for CameraProperties {
+
+/// How the camera moves, `C` switches to the next mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Follows behind the hero, scrolling zooms in and out
+    Chase,
+    /// Looks straight down on the whole arena
+    Overview,
+    /// Moved with the arrow and page keys, looks around while the right mouse button is held
+    FreeFly,
+}
+
+impl Default for CameraMode {
     fn default() -> Self {
-        Self {
-            distance_to_hero: 5.0,
+        CameraMode::Chase
+    }
+}
+
+impl CameraMode {
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::Chase => CameraMode::Overview,
+            CameraMode::Overview => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Chase,
         }
     }
 }
@@ -26,12 +55,15 @@ pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_startup_system(setup_camera.system())
+            .add_system(camera_mode_input_system.system())
             .add_system(camera_follow_system.system())
-            .add_system(camera_zoom_system.system());
+            .add_system(camera_zoom_system.system())
+            .add_system(camera_overview_system.system())
+            .add_system(camera_free_fly_system.system());
     }
 }
 
-fn setup_camera(commands: &mut Commands) {
+fn setup_camera(commands: &mut Commands, camera_settings: Res<CameraSettings>) {
     commands
         .spawn(Camera3dBundle {
             perspective_projection: PerspectiveProjection {
@@ -41,14 +73,33 @@ fn setup_camera(commands: &mut Commands) {
             },
             ..Default::default()
         })
-        .with(CameraProperties::default());
+        .with(CameraProperties {
+            distance_to_hero: camera_settings.distance_to_hero,
+        })
+        .with(CameraMode::default());
+}
+
+fn camera_mode_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut cameras: Query<&mut CameraMode, With<Camera>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::C) {
+        for mut mode in cameras.iter_mut() {
+            *mode = mode.next();
+        }
+    }
+}
+
+/// Moves the camera part of the way towards the target, the larger `lerp` the closer it gets.
+fn move_towards(camera: &mut Transform, target: Vec3, lerp: f32) {
+    camera.translation += (target - camera.translation) * lerp;
 }
 
 fn camera_follow_system(
     time: Res<Time>,
     game_cameras: Res<GameCameras>,
     players: Query<&Transform, With<Hero>>,
-    mut cameras: Query<(&mut Transform, &CameraProperties), With<Camera>>,
+    mut cameras: Query<(&mut Transform, &CameraProperties, &CameraMode), With<Camera>>,
 ) {
     let dt = time.delta_seconds();
     let lerp = (10.0 * dt).min(game_cameras.platform_lerp);
@@ -56,18 +107,16 @@ fn camera_follow_system(
         let p_translation = player.translation;
         let Vec3 { x, z, .. } = vector_for_rotation_y(&player.rotation);
 
-        for (mut camera, props) in cameras.iter_mut() {
+        for (mut camera, props, mode) in cameras.iter_mut() {
+            if *mode != CameraMode::Chase {
+                continue;
+            }
             let camera_target = Vec3::new(
                 p_translation.x + (props.distance_to_hero * x),
                 p_translation.y + props.distance_to_hero * 0.3,
                 p_translation.z + (props.distance_to_hero * z),
             );
-            let dx = (camera_target.x - camera.translation.x) * lerp;
-            let dy = (camera_target.y - camera.translation.y) * lerp;
-            let dz = (camera_target.z - camera.translation.z) * lerp;
-            camera.translation.x += dx;
-            camera.translation.y += dy;
-            camera.translation.z += dz;
+            move_towards(&mut camera, camera_target, lerp);
             camera.look_at(p_translation, Vec3::unit_y());
         }
     }
@@ -79,16 +128,23 @@ struct MouseState {
 
 fn camera_zoom_system(
     mut state: Local<MouseState>,
+    camera_settings: Res<CameraSettings>,
     mouse_wheel_events: Res<Events<MouseWheel>>,
-    mut cameras: Query<&mut CameraProperties, With<Camera>>,
+    mut cameras: Query<(&mut CameraProperties, &CameraMode), With<Camera>>,
 ) {
-    let scroll_factor = 0.02;
-    let min_distance = 3.0;
-    let max_distance = 40.0;
+    let CameraSettings {
+        min_distance,
+        max_distance,
+        zoom_speed,
+        ..
+    } = *camera_settings;
 
     for event in state.mouse_wheel_event_reader.iter(&mouse_wheel_events) {
-        for mut props in cameras.iter_mut() {
-            props.distance_to_hero -= event.y * scroll_factor;
+        for (mut props, mode) in cameras.iter_mut() {
+            if *mode != CameraMode::Chase {
+                continue;
+            }
+            props.distance_to_hero -= event.y * zoom_speed;
             if props.distance_to_hero > max_distance {
                 props.distance_to_hero = max_distance;
             }
@@ -98,3 +154,101 @@ fn camera_zoom_system(
         }
     }
 }
+
+/// Height above the floor at which the field of view covers the whole arena.
+fn overview_height(arena: &Arena, tile_size: f32, projection: &PerspectiveProjection) -> f32 {
+    let half_width = arena.ncols as f32 * tile_size / 2.0;
+    let half_depth = arena.nrows as f32 * tile_size / 2.0;
+    let tan_half_fov = (projection.fov / 2.0).tan();
+    let height =
+        (half_depth / tan_half_fov).max(half_width / (tan_half_fov * projection.aspect_ratio));
+    height * OVERVIEW_MARGIN
+}
+
+fn camera_overview_system(
+    time: Res<Time>,
+    game_cameras: Res<GameCameras>,
+    game_render: Res<GameRender>,
+    arena: Res<Arena>,
+    mut cameras: Query<(&mut Transform, &PerspectiveProjection, &CameraMode), With<Camera>>,
+) {
+    let lerp = (10.0 * time.delta_seconds()).min(game_cameras.platform_lerp);
+    let tile_size = game_render.tile_size as f32;
+    // tile centers are at multiples of the tile size with rows going towards negative z
+    let center = Vec3::new(
+        (arena.ncols as f32 - 1.0) * tile_size / 2.0,
+        0.0,
+        -(arena.nrows as f32 - 1.0) * tile_size / 2.0,
+    );
+    for (mut camera, projection, mode) in cameras.iter_mut() {
+        if *mode != CameraMode::Overview {
+            continue;
+        }
+        let height = overview_height(&arena, tile_size, projection);
+        move_towards(&mut camera, center + Vec3::new(0.0, height, 0.0), lerp);
+        // the first row is at the bottom of the screen
+        camera.look_at(center, -Vec3::unit_z());
+    }
+}
+
+#[derive(Default)]
+struct FreeFlyState {
+    mouse_motion_event_reader: EventReader<MouseMotion>,
+}
+
+fn camera_free_fly_system(
+    mut state: Local<FreeFlyState>,
+    time: Res<Time>,
+    camera_settings: Res<CameraSettings>,
+    controls: Res<ControlSettings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    mouse_motion_events: Res<Events<MouseMotion>>,
+    mut cameras: Query<(&mut Transform, &CameraMode), With<Camera>>,
+) {
+    let motion = state
+        .mouse_motion_event_reader
+        .iter(&mouse_motion_events)
+        .fold(Vec2::zero(), |delta, event| delta + event.delta);
+    let look = if mouse_button_input.pressed(MouseButton::Right) {
+        motion
+    } else {
+        Vec2::zero()
+    };
+
+    for (mut camera, mode) in cameras.iter_mut() {
+        if *mode != CameraMode::FreeFly {
+            continue;
+        }
+        let yaw = Quat::from_rotation_y(-look.x * controls.mouse_sensitivity);
+        let pitch = Quat::from_rotation_x(-look.y * controls.mouse_sensitivity);
+        camera.rotation = yaw * camera.rotation * pitch;
+
+        // cameras look along their negative z axis
+        let forward = camera.rotation * -Vec3::unit_z();
+        let right = camera.rotation * Vec3::unit_x();
+        let mut direction = Vec3::zero();
+        if keyboard_input.pressed(KeyCode::Up) {
+            direction += forward;
+        }
+        if keyboard_input.pressed(KeyCode::Down) {
+            direction -= forward;
+        }
+        if keyboard_input.pressed(KeyCode::Right) {
+            direction += right;
+        }
+        if keyboard_input.pressed(KeyCode::Left) {
+            direction -= right;
+        }
+        if keyboard_input.pressed(KeyCode::PageUp) {
+            direction += Vec3::unit_y();
+        }
+        if keyboard_input.pressed(KeyCode::PageDown) {
+            direction -= Vec3::unit_y();
+        }
+        if direction != Vec3::zero() {
+            camera.translation +=
+                direction.normalize() * camera_settings.fly_speed * time.delta_seconds();
+        }
+    }
+}
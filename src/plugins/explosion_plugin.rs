@@ -0,0 +1,254 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::{Arena, Tilepath},
+    ecs::{
+        components::{
+            BombBag, BombPickup, Explosive, Fading, Fuse, Hero, Invulnerable, LevelEntity,
+            LifeCycle, Projectile, Wreck,
+        },
+        events::{BombDroppedEvent, ExplosionEvent, ImpulseEvent},
+        resources::{PositionConverter, Sniper},
+    },
+};
+
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::{pbr_components, GameRender, GameTime},
+};
+
+/// Seconds from dropping a bomb until it goes off
+const BOMB_FUSE_SECONDS: f32 = 3.0;
+/// Seconds the flash of an explosion is shown
+const EXPLOSION_FLASH_SECONDS: f32 = 0.15;
+
+#[derive(Default)]
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<ExplosionEvent>()
+            .add_event::<BombDroppedEvent>()
+            .on_state_enter(
+                APP_STATE_STAGE,
+                AppState::Loading,
+                setup_bomb_pickups.system(),
+            )
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, pick_up_bombs.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, drop_bombs.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, burn_fuses.system())
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                apply_explosions.system(),
+            )
+            // explosives killed during the update are despawned at the end of the post update
+            .add_system_to_stage(stage::POST_UPDATE, detonate_explosives.system());
+    }
+}
+
+#[derive(Default)]
+pub struct ExplosionRenderPlugin;
+
+impl Plugin for ExplosionRenderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup_bomb_visuals.system())
+            .add_system(show_explosions.system())
+            .add_system_to_stage(stage::POST_UPDATE, show_bombs.system());
+    }
+}
+
+/// Meshes and materials shared by all bombs and explosions.
+struct BombVisuals {
+    mesh: Handle<Mesh>,
+    pickup: Handle<StandardMaterial>,
+    armed: Handle<StandardMaterial>,
+    /// Unit sphere scaled to the blast radius of each explosion
+    explosion: Handle<Mesh>,
+    flash: Handle<StandardMaterial>,
+}
+
+fn setup_bomb_pickups(commands: &mut Commands, game_render: Res<GameRender>, arena: Res<Arena>) {
+    for tile in arena.bombs.iter() {
+        let mut pos = tile.to_world_position(game_render.tile_size);
+        pos.y = game_render.tile_size as f32 * 0.25;
+        let transform: Transform = pos.into();
+        commands
+            .spawn((transform, GlobalTransform::default()))
+            .with(BombPickup)
+            .with(LevelEntity);
+    }
+}
+
+fn pick_up_bombs(
+    commands: &mut Commands,
+    converter: Res<PositionConverter>,
+    mut hero_query: Query<(&Transform, &mut BombBag), (With<Hero>, Without<Wreck>)>,
+    pickup_query: Query<(Entity, &Transform), With<BombPickup>>,
+) {
+    if let Some((hero_transform, mut bomb_bag)) = hero_query.iter_mut().next() {
+        let hero_tile = match converter.tile_from_translation(&hero_transform.translation) {
+            Some(tile) => tile,
+            None => return,
+        };
+        for (entity, transform) in pickup_query.iter() {
+            let on_hero_tile = converter
+                .tile_from_translation(&transform.translation)
+                .map_or(false, |tile| tile.is_same_tile(&hero_tile));
+            if on_hero_tile {
+                bomb_bag.bombs += 1;
+                commands.despawn(entity);
+            }
+        }
+    }
+}
+
+fn drop_bombs(
+    commands: &mut Commands,
+    game_render: Res<GameRender>,
+    mut event_reader: Local<EventReader<BombDroppedEvent>>,
+    bomb_dropped_events: Res<Events<BombDroppedEvent>>,
+) {
+    for event in event_reader.iter(&bomb_dropped_events) {
+        let mut translation = event.position;
+        translation.y = game_render.tile_size as f32 * 0.25;
+        commands
+            .spawn((
+                Transform::from_translation(translation),
+                GlobalTransform::default(),
+            ))
+            .with(Fuse {
+                seconds_left: BOMB_FUSE_SECONDS,
+            })
+            .with(Explosive::bomb())
+            .with(LifeCycle::default())
+            .with(LevelEntity);
+    }
+}
+
+fn burn_fuses(game_time: Res<GameTime>, mut fuse_query: Query<(&mut Fuse, &mut LifeCycle)>) {
+    let dt = game_time.delta_seconds();
+    for (mut fuse, mut life) in fuse_query.iter_mut() {
+        fuse.seconds_left -= dt;
+        if fuse.seconds_left <= 0.0 {
+            life.terminate();
+        }
+    }
+}
+
+fn detonate_explosives(
+    explosive_query: Query<(&Explosive, &LifeCycle, &Transform)>,
+    mut explosion_events: ResMut<Events<ExplosionEvent>>,
+) {
+    for (explosive, life, transform) in explosive_query.iter() {
+        if !life.is_alive() {
+            explosion_events.send(ExplosionEvent {
+                center: transform.translation,
+                explosive: explosive.clone(),
+            });
+        }
+    }
+}
+
+fn apply_explosions(
+    game_render: Res<GameRender>,
+    sniper: Res<Sniper>,
+    tilepath: Res<Tilepath>,
+    mut event_reader: Local<EventReader<ExplosionEvent>>,
+    explosion_events: Res<Events<ExplosionEvent>>,
+    mut target_query: Query<
+        (Entity, &mut LifeCycle, &Transform),
+        (Without<Projectile>, Without<Fading>, Without<Invulnerable>),
+    >,
+    mut impulse_events: ResMut<Events<ImpulseEvent>>,
+) {
+    let tile_size = game_render.tile_size as f32;
+    for event in event_reader.iter(&explosion_events) {
+        let ExplosionEvent { center, explosive } = event;
+        for (entity, mut life, transform) in target_query.iter_mut() {
+            if !life.is_alive() {
+                continue;
+            }
+            let mut away = transform.translation - *center;
+            away.y = 0.0;
+            let distance = away.length() / tile_size;
+            if distance >= explosive.radius
+                || !sniper.has_line_of_sight(&tilepath, center, &transform.translation)
+            {
+                continue;
+            }
+            life.deduct(explosive.damage_at(distance));
+            if away.length() > 0.0 {
+                impulse_events.send(ImpulseEvent {
+                    entity,
+                    impulse: away.normalize() * explosive.knockback_at(distance) * tile_size,
+                });
+            }
+        }
+    }
+}
+
+fn setup_bomb_visuals(
+    commands: &mut Commands,
+    game_render: Res<GameRender>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(BombVisuals {
+        mesh: meshes.add(Mesh::from(shape::Icosphere {
+            radius: game_render.tile_size as f32 / 4.0,
+            subdivisions: 2,
+        })),
+        pickup: materials.add(Color::rgb(0.2, 0.2, 0.2).into()),
+        armed: materials.add(Color::rgb(0.8, 0.1, 0.1).into()),
+        explosion: meshes.add(Mesh::from(shape::Icosphere {
+            radius: 1.0,
+            subdivisions: 3,
+        })),
+        flash: materials.add(Color::rgba(1.0, 0.5, 0.1, 0.4).into()),
+    });
+}
+
+/// Shows bomb pickups and dropped bombs spawned by the simulation.
+fn show_bombs(
+    commands: &mut Commands,
+    visuals: Res<BombVisuals>,
+    pickup_query: Query<Entity, Added<BombPickup>>,
+    armed_query: Query<Entity, Added<Fuse>>,
+) {
+    for entity in pickup_query.iter() {
+        let components = pbr_components(visuals.mesh.clone(), visuals.pickup.clone());
+        commands.insert(entity, components);
+    }
+    for entity in armed_query.iter() {
+        let components = pbr_components(visuals.mesh.clone(), visuals.armed.clone());
+        commands.insert(entity, components);
+    }
+}
+
+/// Flashes a sphere the size of the blast radius.
+fn show_explosions(
+    commands: &mut Commands,
+    game_render: Res<GameRender>,
+    visuals: Res<BombVisuals>,
+    mut event_reader: Local<EventReader<ExplosionEvent>>,
+    explosion_events: Res<Events<ExplosionEvent>>,
+) {
+    let tile_size = game_render.tile_size as f32;
+    for ExplosionEvent { center, explosive } in event_reader.iter(&explosion_events) {
+        let mut transform = Transform::from_translation(*center);
+        transform.scale = Vec3::splat(explosive.radius * tile_size);
+        commands
+            .spawn(PbrBundle {
+                mesh: visuals.explosion.clone(),
+                material: visuals.flash.clone(),
+                transform,
+                ..Default::default()
+            })
+            .with(Fading {
+                seconds_left: EXPLOSION_FLASH_SECONDS,
+            })
+            .with(LifeCycle::default())
+            .with(LevelEntity);
+    }
+}
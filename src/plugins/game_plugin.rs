@@ -1,13 +1,35 @@
-use std::{path::PathBuf, process};
+use std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+};
 
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    render::{
+        draw::{Draw, Visible},
+        pipeline::RenderPipelines,
+        render_graph::base::MainPass,
+    },
+    window::WindowMode,
+};
 
 use crate::{
-    ai::create_tile_caster,
-    arena::{Arena, Tilepath},
-    ecs::resources::{PositionConverter, Sniper},
+    ai::{create_tile_caster, HierarchicalTilepath, DEFAULT_CLUSTER_SIZE},
+    arena::{Arena, Tilepath, WallThemes},
+    ecs::{
+        components::{Hero, LevelEntity},
+        events::LoadLevelEvent,
+        resources::{
+            DisplayMode, EnemyArchetypes, GameRng, InputRecorder, InputRecording, InputReplay,
+            LevelFileWatch, PositionConverter, ProjectilePool, RecordingHeader, Settings, Sniper,
+            LEVEL_FILE_POLL_INTERVAL,
+        },
+    },
 };
 
+use super::{app_state_plugin::AppState, headless_plugin::TICK_SECONDS};
+
 pub struct GameRender {
     pub tile_size: u32,
     pub tile_render_size: f32,
@@ -23,7 +45,6 @@ pub struct GameAsset {
 
 pub struct GameAssets {
     pub floor_tiles: GameAsset,
-    pub wall_metal: GameAsset,
     pub hero: GameAsset,
 }
 
@@ -37,13 +58,6 @@ impl Default for GameAssets {
                 cols: 8,
                 path: "textures/bg/floor-tiles.png".into(),
             },
-            wall_metal: GameAsset {
-                width: 48.0,
-                height: 48.0,
-                rows: 1,
-                cols: 1,
-                path: "textures/bg/wall-metal.png".into(),
-            },
             hero: GameAsset {
                 width: 192.0,
                 height: 192.0,
@@ -55,6 +69,126 @@ impl Default for GameAssets {
     }
 }
 
+pub const ENEMY_ARCHETYPES_PATH: &str = "archetypes/enemies.ron";
+pub const WALL_THEMES_PATH: &str = "themes/walls.ron";
+pub const SETTINGS_PATH: &str = "settings.ron";
+
+/// Resolves a path inside the assets folder the same way the bevy asset server does.
+pub fn asset_path(relative: &str) -> PathBuf {
+    let root = match env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_dir) => PathBuf::from(manifest_dir),
+        Err(_) => env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+            .unwrap_or_default(),
+    };
+    root.join("assets").join(relative)
+}
+
+/// Arena of a level together with the path finding and line of sight resources built from it.
+pub struct LevelResources {
+    pub arena: Arena,
+    pub tile_path: Tilepath,
+    pub hierarchical_tile_path: HierarchicalTilepath,
+    pub sniper: Sniper,
+}
+
+impl LevelResources {
+    pub fn load(
+        level: &str,
+        tile_size: u32,
+        converter: &PositionConverter,
+        enemy_archetypes: &EnemyArchetypes,
+        wall_themes: &WallThemes,
+    ) -> Result<LevelResources, Box<dyn Error>> {
+        let arena = Arena::for_level(level, tile_size)?;
+        LevelResources::new(arena, tile_size, converter, enemy_archetypes, wall_themes)
+    }
+
+    pub fn new(
+        arena: Arena,
+        tile_size: u32,
+        converter: &PositionConverter,
+        enemy_archetypes: &EnemyArchetypes,
+        wall_themes: &WallThemes,
+    ) -> Result<LevelResources, Box<dyn Error>> {
+        enemy_archetypes.validate_spawns(&arena)?;
+        if wall_themes.get(&arena.wall_theme).is_none() {
+            return Err(format!("unknown wall theme '{}'", arena.wall_theme).into());
+        }
+
+        let tile_path = Tilepath::from_arena(&arena);
+        let hierarchical_tile_path =
+            HierarchicalTilepath::from_tilepath(&tile_path, DEFAULT_CLUSTER_SIZE);
+        let tile_caster = create_tile_caster(arena.ncols, arena.nrows, tile_size as f32);
+        let sniper = Sniper::new(tile_caster, converter.clone());
+        Ok(LevelResources {
+            arena,
+            tile_path,
+            hierarchical_tile_path,
+            sniper,
+        })
+    }
+}
+
+/// Seconds the simulation advances per tick, fixed for headless runs so they play out the same
+/// every time.
+pub struct GameTime {
+    delta_seconds: f32,
+    fixed_delta: Option<f32>,
+}
+
+impl Default for GameTime {
+    fn default() -> Self {
+        Self {
+            delta_seconds: 0.0,
+            fixed_delta: None,
+        }
+    }
+}
+
+impl GameTime {
+    pub fn fixed(delta_seconds: f32) -> Self {
+        Self {
+            delta_seconds,
+            fixed_delta: Some(delta_seconds),
+        }
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta_seconds
+    }
+}
+
+/// Render components of a [PbrBundle] without its transforms, used to show entities spawned by
+/// the simulation.
+pub fn pbr_components(
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+) -> (
+    Handle<Mesh>,
+    Handle<StandardMaterial>,
+    MainPass,
+    Draw,
+    Visible,
+    RenderPipelines,
+) {
+    let PbrBundle {
+        mesh,
+        material,
+        main_pass,
+        draw,
+        visible,
+        render_pipelines,
+        ..
+    } = PbrBundle {
+        mesh,
+        material,
+        ..Default::default()
+    };
+    (mesh, material, main_pass, draw, visible, render_pipelines)
+}
+
 pub struct GameCameras {
     pub platform_lerp: f32,
 }
@@ -65,58 +199,195 @@ impl Default for GameCameras {
     }
 }
 
+/// Sets up the game from the settings file, the given options override the settings.
 #[derive(Default)]
-pub struct GamePlugin;
+pub struct GamePlugin {
+    pub level: Option<String>,
+    pub mode: Option<DisplayMode>,
+    /// Seeds the random numbers of the game, `0` if not given
+    pub seed: Option<u64>,
+    /// File the input of the played level is recorded to
+    pub record: Option<PathBuf>,
+    /// Recording to replay, its level and seed override the ones given
+    pub replay: Option<PathBuf>,
+}
 
-const SMALL: bool = true;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        let tile_size = 1;
-        let render_scale = 0.1;
-        let tile_render_size = render_scale * tile_size as f32;
+        let mut settings =
+            Settings::load(&asset_path(SETTINGS_PATH)).expect("FATAL: unable to load settings");
+        if let Some(level) = &self.level {
+            settings.level = level.clone();
+        }
+        let mut seed = self.seed.unwrap_or_default();
+        let replay = match &self.replay {
+            Some(path) => {
+                let recording = InputRecording::load(path).expect("FATAL: unable to load replay");
+                settings.level = recording.header.level;
+                seed = recording.header.seed;
+                InputReplay::new(recording.frames)
+            }
+            None => InputReplay::default(),
+        };
+        let recorder = match &self.record {
+            Some(path) => {
+                let header = RecordingHeader {
+                    level: settings.level.clone(),
+                    seed,
+                };
+                InputRecorder::create(path, &header).expect("FATAL: unable to create recording")
+            }
+            None => InputRecorder::default(),
+        };
+        // recorded runs advance in fixed ticks so that replaying them plays out the same
+        let game_time = if self.record.is_some() || self.replay.is_some() {
+            GameTime::fixed(TICK_SECONDS)
+        } else {
+            GameTime::default()
+        };
+        if let Some(mode) = self.mode {
+            settings.window.mode = mode;
+        }
+        let tile_size = settings.render.tile_size;
+        let tile_render_size = settings.render.render_scale * tile_size as f32;
         let render = GameRender {
             tile_size,
             tile_render_size,
         };
 
         let converter = PositionConverter::new(tile_size);
-        let arena =
-            Arena::for_level("face off", render.tile_size).expect("FATAL: unable to create arena");
-
-        let tile_path = Tilepath::from_arena(&arena);
-        let tile_caster = create_tile_caster(arena.ncols, arena.nrows, tile_size as f32);
-        let sniper = Sniper::new(tile_caster, converter.clone());
+        let enemy_archetypes = EnemyArchetypes::load(&asset_path(ENEMY_ARCHETYPES_PATH))
+            .expect("FATAL: unable to load enemy archetypes");
+        let wall_themes = WallThemes::load(&asset_path(WALL_THEMES_PATH))
+            .expect("FATAL: unable to load wall themes");
+        let level = LevelResources::load(
+            &settings.level,
+            render.tile_size,
+            &converter,
+            &enemy_archetypes,
+            &wall_themes,
+        )
+        .expect("FATAL: unable to load level");
 
-        let (width, height) = if SMALL {
-            (640.0, 480.0)
-        } else {
-            (1024.0, 768.0)
-        };
         app.init_resource::<GameCameras>()
             .init_resource::<GameAssets>()
             .init_resource::<GameCameras>()
-            .add_resource(sniper)
+            .add_resource(level.sniper)
             .add_resource(render)
             .add_resource(converter)
-            .add_resource(tile_path)
+            .add_resource(level.tile_path)
+            .add_resource(level.hierarchical_tile_path)
             .add_resource(WindowDescriptor {
                 title: "batufo".to_string(),
-                width,
-                height,
-                vsync: false,
-                resizable: true,
+                width: settings.window.width,
+                height: settings.window.height,
+                vsync: settings.window.vsync,
+                resizable: settings.window.resizable,
                 decorations: true,
                 cursor_locked: false,
                 cursor_visible: true,
+                mode: window_mode(settings.window.mode),
                 ..Default::default()
             })
-            .add_resource(arena)
-            .add_system(exit_game_system.system());
+            .add_resource(settings.camera)
+            .add_resource(settings.controls)
+            .add_resource(level.arena)
+            .add_resource(enemy_archetypes)
+            .add_resource(wall_themes)
+            .init_resource::<LevelFileWatch>()
+            .add_resource(game_time)
+            .add_resource(GameRng::new(seed))
+            .add_resource(recorder)
+            .add_resource(replay)
+            .add_event::<LoadLevelEvent>()
+            .add_system_to_stage(stage::PRE_UPDATE, advance_game_time.system())
+            .add_system(reload_level_file.system())
+            .add_system(load_level.system());
+    }
+}
+
+fn window_mode(mode: DisplayMode) -> WindowMode {
+    match mode {
+        DisplayMode::Windowed => WindowMode::Windowed,
+        DisplayMode::BorderlessFullscreen => WindowMode::BorderlessFullscreen,
+        DisplayMode::Fullscreen => WindowMode::Fullscreen { use_size: true },
+    }
+}
+
+fn advance_game_time(time: Res<Time>, mut game_time: ResMut<GameTime>) {
+    game_time.delta_seconds = game_time
+        .fixed_delta
+        .unwrap_or_else(|| time.delta_seconds());
+}
+
+/// Tears down the entities of the current level and replaces its resources,
+/// the entities of the new level are spawned when loading.
+fn load_level(
+    commands: &mut Commands,
+    mut event_reader: Local<EventReader<LoadLevelEvent>>,
+    load_level_events: Res<Events<LoadLevelEvent>>,
+    render: Res<GameRender>,
+    converter: Res<PositionConverter>,
+    enemy_archetypes: Res<EnemyArchetypes>,
+    wall_themes: Res<WallThemes>,
+    mut state: ResMut<State<AppState>>,
+    mut pool: ResMut<ProjectilePool>,
+    level_entities_query: Query<(Entity, Option<&Hero>), With<LevelEntity>>,
+) {
+    let event = match event_reader.iter(&load_level_events).last() {
+        Some(event) => event,
+        None => return,
+    };
+    let level = match LevelResources::load(
+        &event.level,
+        render.tile_size,
+        &converter,
+        &enemy_archetypes,
+        &wall_themes,
+    ) {
+        Ok(level) => level,
+        Err(err) => {
+            error!("unable to load level '{}': {}", event.level, err);
+            return;
+        }
+    };
+
+    for (entity, hero) in level_entities_query.iter() {
+        if hero.is_none() || !event.keep_hero {
+            commands.despawn_recursive(entity);
+        }
     }
+    pool.clear();
+    commands.insert_resource(level.arena);
+    commands.insert_resource(level.tile_path);
+    commands.insert_resource(level.hierarchical_tile_path);
+    commands.insert_resource(level.sniper);
+    // loading the level wins over any other transition queued this frame
+    state.overwrite_next(AppState::Loading).ok();
 }
 
-fn exit_game_system(keyboard_input: Res<Input<KeyCode>>) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        process::exit(0)
+/// Reloads a level loaded from a file in place once the file changed.
+fn reload_level_file(
+    time: Res<Time>,
+    arena: Res<Arena>,
+    state: Res<State<AppState>>,
+    mut since_poll: Local<f32>,
+    mut watch: ResMut<LevelFileWatch>,
+    mut load_level_events: ResMut<Events<LoadLevelEvent>>,
+) {
+    *since_poll += time.delta_seconds();
+    if *since_poll < LEVEL_FILE_POLL_INTERVAL {
+        return;
+    }
+    *since_poll = 0.0;
+
+    let changed = watch.poll(Path::new(&arena.level));
+    let in_level = matches!(state.current(), AppState::Playing | AppState::Paused);
+    if changed && in_level {
+        info!("level file '{}' changed, reloading", arena.level);
+        load_level_events.send(LoadLevelEvent {
+            level: arena.level.clone(),
+            keep_hero: true,
+        });
     }
 }
@@ -3,176 +3,279 @@ use std::f32::consts::PI;
 use bevy::prelude::*;
 
 use crate::{
-    ai::{find_path, Shot},
+    ai::{find_path, retreat_from, HierarchicalTilepath, Shot},
     animations::{
         Movement, MovementAnimation, MovementAxis, RollingBoxAnimation, RotationAxis, Spin,
     },
     arena::{Arena, Tilepath},
     ecs::{
         components::{
-            Hero, HeroFollower, HeroShooter, MovementState, OrthogonalMovement, ProjectileSpawner,
+            normalize_angle, Behavior, BehaviorState, Faction, HeadLights, Hero, HeroFollower,
+            HeroShooter, Knockback, LevelEntity, LifeCycle, Mass, MovementState, Observation,
+            OrthogonalMovement, Patrol, Perception, ProjectileSpawner, Wreck,
+        },
+        events::{HitscanRequestedEvent, ProjectileRequestedEvent},
+        resources::{
+            EnemyArchetypes, GameRng, MeshSettings, MovementSettings, PositionConverter, Sniper,
         },
-        events::ProjectileRequestedEvent,
-        resources::{PositionConverter, Sniper},
     },
     engine::TilePosition,
 };
 
-use super::game_plugin::{GameAssets, GameRender};
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::{pbr_components, GameRender, GameTime},
+    projectile_plugin::fire_weapon,
+};
 
 #[derive(Default)]
 pub struct GunTowerPlugin;
 
 impl Plugin for GunTowerPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(gun_tower_setup.system())
-            .add_system(follow_hero.system())
-            .add_system(shoot_hero.system());
+        app.on_state_enter(APP_STATE_STAGE, AppState::Loading, gun_tower_setup.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, perceive_hero.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, update_behavior.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, move_followers.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, shoot_hero.system());
+    }
+}
+
+#[derive(Default)]
+pub struct GunTowerRenderPlugin;
+
+impl Plugin for GunTowerRenderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(stage::POST_UPDATE, setup_gun_tower_visuals.system());
     }
 }
 
 fn gun_tower_setup(
     commands: &mut Commands,
-    _game_assets: Res<GameAssets>,
     game_render: Res<GameRender>,
-    _arena: Res<Arena>,
-    _asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    arena: Res<Arena>,
+    archetypes: Res<EnemyArchetypes>,
 ) {
-    //
-    // Moving Tower
-    //
-    let mut pos = TilePosition::centered(20, 20, game_render.tile_size)
-        .to_world_position(game_render.tile_size);
     let size = game_render.tile_size as f32;
+    for spawn in arena.enemy_spawns.iter() {
+        let archetype = archetypes
+            .get(&spawn.archetype)
+            .expect("enemy spawns are validated when loading archetypes");
+        let (col, row) = spawn.tile;
+        let mut pos = TilePosition::centered(col, row, game_render.tile_size)
+            .to_world_position(game_render.tile_size);
+        pos.y = size * archetype.mesh.y_offset;
 
-    pos.y = size * 0.6;
-
-    commands
-        .spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(size, size, size))),
-            material: {
-                let material = materials.add(Color::rgb(0.9, 0.4, 0.2).into());
-                material
-            },
-            transform: {
-                let transform: Transform = (&pos).into();
-                transform
-            },
-            ..Default::default()
-        })
-        .with(OrthogonalMovement {
-            center_y: (&pos).y.clone(),
-            ..Default::default()
-        })
-        .with(HeroFollower)
-        .with(HeroShooter)
-        .with(ProjectileSpawner {
-            range: 15_f32.powi(2),
-            ..Default::default()
-        });
-
-    //
-    // Stationary Tower
-    //
-    let mut pos = TilePosition::centered(20, 40, game_render.tile_size)
-        .to_world_position(game_render.tile_size);
-    let size = game_render.tile_size as f32;
+        let transform: Transform = (&pos).into();
+        commands
+            .spawn((transform, GlobalTransform::default()))
+            .with(archetype.mesh.clone())
+            .with(Faction::Enemy)
+            .with(LevelEntity)
+            .with(archetype.behavior())
+            .with(archetype.perception())
+            .with(archetype.life_cycle());
 
-    pos.y = size * 0.6;
-    commands
-        .spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(size, size * 2.0, size))),
-            material: {
-                let material = materials.add(Color::rgb(0.9, 0.4, 0.2).into());
-                material
-            },
-            transform: {
-                let transform: Transform = (&pos).into();
-                transform
-            },
-            ..Default::default()
-        })
-        .with(HeroShooter)
-        .with(ProjectileSpawner {
-            range: 25_f32.powi(2),
-            ..Default::default()
-        });
+        if let Some(explosive) = &archetype.explodes {
+            commands.with(explosive.clone());
+        }
+        if let Some(spawner) = archetype.projectile_spawner() {
+            commands.with(HeroShooter).with(spawner);
+        }
+        if let MovementSettings::Rolling { step_factor } = archetype.movement {
+            commands
+                .with(HeroFollower)
+                .with(OrthogonalMovement {
+                    step_factor,
+                    center_y: pos.y,
+                    ..Default::default()
+                })
+                .with(Mass(archetype.mass))
+                .with(Knockback::default());
+        }
+        if let Some(route) = spawn
+            .patrol_route
+            .as_ref()
+            .and_then(|name| arena.patrol_route(name))
+        {
+            commands.with(Patrol::new(route.clone()));
+        }
+    }
+}
+
+fn perceive_hero(
+    time: Res<GameTime>,
+    sniper: Res<Sniper>,
+    tilepath: Res<Tilepath>,
+    converter: Res<PositionConverter>,
+    mut perception_query: Query<(&Transform, &mut Perception)>,
+    hero_query: Query<(&Transform, &HeadLights), (With<Hero>, Without<Wreck>)>,
+) {
+    let hero = hero_query.iter().next();
+    let hero_transform = hero.map(|(transform, _)| transform);
+    let hero_lit = hero.map_or(false, |(_, head_lights)| head_lights.0);
+    for (transform, mut perception) in perception_query.iter_mut() {
+        let sighting = hero_transform
+            .and_then(|hero_transform| {
+                let (shot, _) = sniper.find_shot(
+                    &tilepath,
+                    &transform,
+                    &hero_transform,
+                    Some(perception.effective_view_distance(hero_lit)),
+                )?;
+                let hero_tile = converter.tile_from_translation(&hero_transform.translation)?;
+                Some((shot, hero_tile))
+            })
+            .filter(|(shot, _)| perception.in_view(shot.direction, shot.distance, hero_lit));
+
+        match sighting {
+            Some((shot, hero_tile)) => perception.spot(hero_tile, shot.direction, shot.distance),
+            None => perception.lose_sight(time.delta_seconds()),
+        }
+    }
+}
+
+fn update_behavior(
+    time: Res<GameTime>,
+    converter: Res<PositionConverter>,
+    mut behavior_query: Query<(
+        &Transform,
+        &mut Behavior,
+        &mut Perception,
+        Option<&LifeCycle>,
+        Option<&ProjectileSpawner>,
+    )>,
+) {
+    for (transform, mut behavior, mut perception, life, spawner) in behavior_query.iter_mut() {
+        let hero_in_range = perception.can_see_target
+            && spawner.map_or(false, |spawner| {
+                perception.target_distance.powi(2) <= spawner.range
+            });
+        let tile = converter.tile_from_translation(&transform.translation);
+        let investigation_done = match (&perception.last_known_position, tile) {
+            (Some(last_known_position), Some(tile)) => last_known_position.is_same_tile(&tile),
+            _ => true,
+        };
+        let observation = Observation {
+            hero_visible: perception.can_see_target,
+            hero_in_range,
+            investigation_done,
+            health_ratio: life.map_or(1.0, |life| life.health_ratio()),
+        };
+        if behavior.update(&observation, time.delta_seconds()) == BehaviorState::Patrol {
+            perception.forget();
+        }
+    }
 }
 
 fn shoot_hero(
     sniper: Res<Sniper>,
     tilepath: Res<Tilepath>,
-    shooter_query: Query<(&Transform, &ProjectileSpawner), With<HeroShooter>>,
-    hero_query: Query<&Transform, With<Hero>>,
-    mut events: ResMut<Events<ProjectileRequestedEvent>>,
+    mut rng: ResMut<GameRng>,
+    mut shooter_query: Query<
+        (&Transform, &mut ProjectileSpawner, &Behavior, &Perception),
+        With<HeroShooter>,
+    >,
+    hero_query: Query<&Transform, (With<Hero>, Without<Wreck>)>,
+    mut projectile_events: ResMut<Events<ProjectileRequestedEvent>>,
+    mut hitscan_events: ResMut<Events<HitscanRequestedEvent>>,
 ) {
     if let Some(hero_transform) = hero_query.iter().next() {
-        for (transform, spawner) in shooter_query.iter() {
-            if !spawner.is_ready() {
+        for (transform, mut spawner, behavior, perception) in shooter_query.iter_mut() {
+            if !spawner.is_ready() || !behavior.can_shoot() {
                 continue;
             }
-            let shot =
-                sniper.find_shot(&tilepath, &transform, &hero_transform, Some(spawner.range));
+            let shot = if perception.can_see_target {
+                sniper.find_shot(&tilepath, &transform, &hero_transform, Some(spawner.range))
+            } else if spawner.bounces > 0 {
+                // hero took cover, try to ricochet a shot to where it was last seen
+                perception.last_known_position.as_ref().and_then(|target| {
+                    sniper.find_bank_shot(
+                        &tilepath,
+                        &transform,
+                        target,
+                        spawner.bounces,
+                        spawner.range.sqrt(),
+                    )
+                })
+            } else {
+                None
+            };
             if let Some((Shot { direction, .. }, origin)) = shot {
-                let event = ProjectileRequestedEvent::new(
-                    origin,
-                    direction,
-                    1.0,
-                    spawner.range,
-                    spawner.health_damage,
+                let miss = rng.range(-spawner.aim_error, spawner.aim_error);
+                fire_weapon(
+                    &mut spawner,
+                    &origin,
+                    normalize_angle(direction + miss),
+                    Faction::Enemy,
+                    &mut projectile_events,
+                    &mut hitscan_events,
                 );
-                events.send(event);
             }
         }
     }
 }
 
-fn follow_hero(
-    time: Res<Time>,
-    game_render: Res<GameRender>,
+fn move_followers(
+    time: Res<GameTime>,
     converter: Res<PositionConverter>,
     tilepath: Res<Tilepath>,
-    mut follower_query: Query<(&mut Transform, &mut OrthogonalMovement), With<HeroFollower>>,
-    hero_query: Query<&Transform, With<Hero>>,
+    hierarchical_tilepath: Res<HierarchicalTilepath>,
+    mut follower_query: Query<
+        (
+            &mut Transform,
+            &mut OrthogonalMovement,
+            &Behavior,
+            &mut Perception,
+            Option<&mut Patrol>,
+            Option<&Knockback>,
+        ),
+        With<HeroFollower>,
+    >,
 ) {
-    for (mut follower_transform, mut follower_movement) in follower_query.iter_mut() {
+    for (
+        mut follower_transform,
+        mut follower_movement,
+        behavior,
+        mut perception,
+        mut patrol,
+        knockback,
+    ) in follower_query.iter_mut()
+    {
         let step_factor = follower_movement.step_factor;
         match follower_movement.state {
+            // wait for the tower to come to rest after being shoved
+            MovementState::Idle if knockback.map_or(false, |k| k.is_moving()) => {}
             MovementState::Idle => {
-                let hero_transform = hero_query.iter().next();
-                if let Some(hero_transform) = hero_transform {
-                    if let (tower_tile, Some(path)) = path_to_hero(
-                        &converter,
-                        &tilepath,
-                        &follower_transform.translation,
-                        &hero_transform.translation,
-                    ) {
-                        follower_movement.state = MovementState::Moving({
-                            let (col, row) = path.first().map(|&(col, row)| (col, row)).unwrap();
-                            let movement_axis =
-                                MovementAxis::from_move_xz(tower_tile.col_row(), (col, row));
-                            let rotation_axis = RotationAxis::from_movement_axis(&movement_axis);
-                            let mut translation = converter.translation_from_col_row((col, row));
-                            translation.y = follower_movement.center_y;
-
-                            let rolling_box_animation = RollingBoxAnimation {
-                                movement: Movement::from_start_end(
-                                    follower_transform.translation,
-                                    translation,
-                                    movement_axis,
-                                ),
-                                spin: Spin::from_delta_angle(
-                                    &Quat::default(),
-                                    rotation_axis,
-                                    PI / 2.0,
-                                ),
-                                percent_complete: 0.0,
-                            };
-                            MovementAnimation::Rolling(rolling_box_animation)
-                        });
+                let tower_tile = converter
+                    .tile_from_translation(&follower_transform.translation)
+                    .expect("gun tower should never leave tilemap");
+                let next_tile = match (behavior.state, &perception.last_known_position) {
+                    (BehaviorState::Chase, Some(target))
+                    | (BehaviorState::Investigate, Some(target)) => hierarchical_tilepath
+                        .find_path(&tilepath, tower_tile.col_row(), target.col_row())
+                        .and_then(|path| path.first().cloned()),
+                    (BehaviorState::Retreat, Some(threat)) => retreat_from(
+                        &tilepath.valid_tiles,
+                        tower_tile.col_row(),
+                        threat.col_row(),
+                    ),
+                    (BehaviorState::Patrol, _) => patrol
+                        .as_mut()
+                        .and_then(|patrol| next_patrol_tile(&tilepath, &tower_tile, patrol)),
+                    _ => None,
+                };
+                if let Some(next_tile) = next_tile {
+                    if !perception.can_see_target {
+                        perception.facing = direction_of_move(tower_tile.col_row(), next_tile);
                     }
+                    follower_movement.state = MovementState::Moving(roll_to_tile(
+                        &converter,
+                        &follower_transform,
+                        follower_movement.center_y,
+                        &tower_tile,
+                        next_tile,
+                    ));
                 }
             }
             MovementState::Moving(ref mut movement) => {
@@ -180,9 +283,8 @@ fn follow_hero(
                 match movement {
                     MovementAnimation::Rolling(rolling) => {
                         if rolling.step_percent(&mut follower_transform, step_percent) {
+                            follower_transform.translation.y = follower_movement.center_y;
                             follower_movement.state = MovementState::Idle;
-                            let size = game_render.tile_size as f32;
-                            follower_transform.translation.y = size * 0.6;
                         }
                     }
                 }
@@ -191,28 +293,73 @@ fn follow_hero(
     }
 }
 
-fn path_to_hero(
+/// Builds the box of a gun tower spawned by the simulation from the mesh of its archetype.
+fn setup_gun_tower_visuals(
+    commands: &mut Commands,
+    game_render: Res<GameRender>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    tower_query: Query<(Entity, &MeshSettings), Added<MeshSettings>>,
+) {
+    let size = game_render.tile_size as f32;
+    for (entity, mesh) in tower_query.iter() {
+        let (width, height, depth) = mesh.size;
+        let (r, g, b) = mesh.color;
+        commands.insert(
+            entity,
+            pbr_components(
+                meshes.add(Mesh::from(shape::Box::new(
+                    size * width,
+                    size * height,
+                    size * depth,
+                ))),
+                materials.add(Color::rgb(r, g, b).into()),
+            ),
+        );
+    }
+}
+
+fn roll_to_tile(
     converter: &PositionConverter,
+    transform: &Transform,
+    center_y: f32,
+    from_tile: &TilePosition,
+    (col, row): (u32, u32),
+) -> MovementAnimation {
+    let movement_axis = MovementAxis::from_move_xz(from_tile.col_row(), (col, row));
+    let rotation_axis = RotationAxis::from_movement_axis(&movement_axis);
+    let mut translation = converter.translation_from_col_row((col, row));
+    translation.y = center_y;
+
+    let rolling_box_animation = RollingBoxAnimation {
+        movement: Movement::from_start_end(transform.translation, translation, movement_axis),
+        spin: Spin::from_delta_angle(&Quat::default(), rotation_axis, PI / 2.0),
+        percent_complete: 0.0,
+    };
+    MovementAnimation::Rolling(rolling_box_animation)
+}
+
+/// Angle of a move between neighbouring tiles, counter clockwise starting at the positive x-axis.
+fn direction_of_move((from_col, from_row): (u32, u32), (to_col, to_row): (u32, u32)) -> f32 {
+    let dx = to_col as f32 - from_col as f32;
+    let dy = to_row as f32 - from_row as f32;
+    normalize_angle(dy.atan2(dx))
+}
+
+/// Next tile along the patrol route, moving on to the following waypoint once one is reached.
+fn next_patrol_tile(
     tilepath: &Tilepath,
-    tower_pos: &Vec3,
-    hero_pos: &Vec3,
-) -> (TilePosition, Option<Vec<(u32, u32)>>) {
-    let tower_tile = converter
-        .tile_from_translation(tower_pos)
-        .expect("gun tower should never leave tilemap");
-
-    let hero_tile = converter.tile_from_translation(hero_pos);
-
-    let path = hero_tile
-        .map(|hero_tile| {
-            find_path(
-                &tilepath.valid_tiles,
-                false,
-                tower_tile.col_row(),
-                hero_tile.col_row(),
-            )
-        })
-        .flatten();
-
-    (tower_tile, path)
+    tower_tile: &TilePosition,
+    patrol: &mut Patrol,
+) -> Option<(u32, u32)> {
+    if patrol.target() == tower_tile.col_row() {
+        patrol.advance();
+    }
+    find_path(
+        &tilepath.valid_tiles,
+        false,
+        tower_tile.col_row(),
+        patrol.target(),
+    )
+    .and_then(|path| path.first().cloned())
 }
@@ -0,0 +1,44 @@
+use bevy::{app::AppExit, prelude::*};
+
+use super::{app_state_plugin::AppState, game_plugin::GameTime};
+
+/// Seconds each tick of a headless run advances the simulation
+pub const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+/// Runs the simulation as fast as possible with a fixed tick, stopping after `ticks` if given.
+#[derive(Default)]
+pub struct HeadlessPlugin {
+    pub ticks: Option<u64>,
+}
+
+struct HeadlessRun {
+    ticks: u64,
+    max_ticks: Option<u64>,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(GameTime::fixed(TICK_SECONDS))
+            .add_resource(HeadlessRun {
+                ticks: 0,
+                max_ticks: self.ticks,
+            })
+            .add_system_to_stage(stage::LAST, count_ticks.system());
+    }
+}
+
+fn count_ticks(
+    mut run: ResMut<HeadlessRun>,
+    state: Res<State<AppState>>,
+    mut exit_events: ResMut<Events<AppExit>>,
+) {
+    run.ticks += 1;
+    if Some(run.ticks) == run.max_ticks {
+        info!(
+            "headless run stopped after {} ticks in state {:?}",
+            run.ticks,
+            state.current()
+        );
+        exit_events.send(AppExit);
+    }
+}
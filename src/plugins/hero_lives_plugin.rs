@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::ecs::components::{Hero, Invulnerable, Knockback, LifeCycle, Lives, Velocity, Wreck};
+
+use super::{
+    app_state_plugin::{queue_state, AppState, APP_STATE_STAGE},
+    game_plugin::{GameRender, GameTime},
+    hero_plugin::HERO_HOVER_HEIGHT,
+};
+
+/// Radians per second the wreck of the hero spins while sinking
+const WRECK_SPIN: f32 = 6.0;
+/// Tiles per second the wreck of the hero sinks into the floor
+const WRECK_SINK: f32 = 0.1;
+
+#[derive(Default)]
+pub struct HeroLivesPlugin;
+
+impl Plugin for HeroLivesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.on_state_update(APP_STATE_STAGE, AppState::Playing, wreck_hero.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, respawn_hero.system())
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                expire_invulnerability.system(),
+            );
+    }
+}
+
+fn wreck_hero(
+    commands: &mut Commands,
+    mut hero_query: Query<
+        (
+            Entity,
+            &LifeCycle,
+            &mut Lives,
+            &mut Velocity,
+            &mut Knockback,
+        ),
+        (With<Hero>, Without<Wreck>),
+    >,
+) {
+    for (entity, life, mut lives, mut velocity, mut knockback) in hero_query.iter_mut() {
+        if life.is_alive() {
+            continue;
+        }
+        lives.lose();
+        velocity.0 = Vec3::zero();
+        knockback.velocity = Vec3::zero();
+        commands.insert_one(entity, Wreck::default());
+    }
+}
+
+/// The wreck sinks into the floor, then the hero respawns at its checkpoint or the game is over.
+fn respawn_hero(
+    commands: &mut Commands,
+    time: Res<GameTime>,
+    game_render: Res<GameRender>,
+    mut state: ResMut<State<AppState>>,
+    mut hero_query: Query<(Entity, &mut Wreck, &Lives, &mut LifeCycle, &mut Transform), With<Hero>>,
+) {
+    let dt = time.delta_seconds();
+    let size = game_render.tile_size as f32;
+    for (entity, mut wreck, lives, mut life, mut transform) in hero_query.iter_mut() {
+        wreck.seconds_left -= dt;
+        if wreck.seconds_left > 0.0 {
+            transform.rotate(Quat::from_rotation_y(WRECK_SPIN * dt));
+            transform.translation.y -= WRECK_SINK * size * dt;
+            continue;
+        }
+        if lives.remaining == 0 {
+            queue_state(&mut state, AppState::GameOver);
+            continue;
+        }
+
+        let mut pos = lives.checkpoint.to_world_position(game_render.tile_size);
+        pos.y = size * HERO_HOVER_HEIGHT;
+        transform.translation = (&pos).into();
+        transform.rotation = Quat::identity();
+        life.revive();
+        commands.remove_one::<Wreck>(entity);
+        commands.insert_one(entity, Invulnerable::default());
+    }
+}
+
+fn expire_invulnerability(
+    commands: &mut Commands,
+    time: Res<GameTime>,
+    mut invulnerable_query: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in invulnerable_query.iter_mut() {
+        if invulnerable.tick(time.delta_seconds()) {
+            commands.remove_one::<Invulnerable>(entity);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::Arena,
+    ecs::{
+        components::{
+            BombBag, Faction, HeadLights, Hero, Knockback, LevelEntity, LifeCycle, Lives, Mass,
+            ProjectileSpawner, Velocity, HERO_LIVES,
+        },
+        resources::PositionConverter,
+    },
+};
+
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::GameRender,
+};
+
+/// Height the hero hovers above the floor relative to the tile size
+pub const HERO_HOVER_HEIGHT: f32 = 0.2;
+
+#[derive(Default)]
+pub struct HeroPlugin;
+
+impl Plugin for HeroPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.on_state_enter(APP_STATE_STAGE, AppState::Loading, setup_hero.system());
+    }
+}
+
+fn setup_hero(
+    commands: &mut Commands,
+    game_render: Res<GameRender>,
+    arena: Res<Arena>,
+    converter: Res<PositionConverter>,
+    mut hero_query: Query<(&mut Transform, &mut Lives), With<Hero>>,
+) {
+    let mut pos = arena.player.to_world_position(game_render.tile_size);
+    let size = game_render.tile_size as f32;
+    pos.y = size * HERO_HOVER_HEIGHT;
+
+    // a hero kept while reloading the level only moves if its tile isn't part of the level anymore
+    if let Some((mut transform, mut lives)) = hero_query.iter_mut().next() {
+        lives.checkpoint = arena.player.clone();
+        let on_floor = converter
+            .tile_from_translation(&transform.translation)
+            .map_or(false, |tile| arena.is_floor_tile((tile.col, tile.row)));
+        if !on_floor {
+            transform.translation = (&pos).into();
+        }
+        return;
+    }
+
+    commands
+        .spawn((
+            {
+                let mut transform: Transform = pos.into();
+                transform.scale = transform.scale * 0.18;
+                transform
+            },
+            GlobalTransform::default(),
+        ))
+        .with(Hero::default())
+        .with(LevelEntity)
+        .with(Faction::Hero)
+        .with(HeadLights(true))
+        .with(BombBag::default())
+        .with(LifeCycle::default())
+        .with(Lives::new(HERO_LIVES, arena.player.clone()))
+        .with(ProjectileSpawner {
+            range: 12_f32.powi(2),
+            cooldown: 0.33,
+            magazine_size: 12,
+            reload_duration: 2.0,
+            health_damage: 10,
+            ..Default::default()
+        })
+        .with(Velocity::default())
+        .with(Mass::default())
+        .with(Knockback::default());
+}
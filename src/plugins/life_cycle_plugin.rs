@@ -1,17 +1,37 @@
 use bevy::prelude::*;
 
-use crate::ecs::components::LifeCycle;
+use crate::ecs::components::{Fading, Hero, LifeCycle, Projectile};
+
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::GameTime,
+};
 
 #[derive(Default)]
 pub struct LifeCyclePlugin;
 
 impl Plugin for LifeCyclePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system_to_stage(stage::POST_UPDATE, despawn_destroyed.system());
+        app.on_state_update(APP_STATE_STAGE, AppState::Playing, fade_out.system())
+            .add_system_to_stage(stage::POST_UPDATE, despawn_destroyed.system());
+    }
+}
+
+fn fade_out(game_time: Res<GameTime>, mut fading_query: Query<(&mut Fading, &mut LifeCycle)>) {
+    let dt = game_time.delta_seconds();
+    for (mut fading, mut life) in fading_query.iter_mut() {
+        fading.seconds_left -= dt;
+        if fading.seconds_left <= 0.0 {
+            life.terminate();
+        }
     }
 }
 
-fn despawn_destroyed(commands: &mut Commands, entities_query: Query<(Entity, &LifeCycle)>) {
+/// Projectiles are left to the projectile plugin which recycles them and the hero respawns.
+fn despawn_destroyed(
+    commands: &mut Commands,
+    entities_query: Query<(Entity, &LifeCycle), (Without<Projectile>, Without<Hero>)>,
+) {
     let need_despawn = entities_query.iter().filter(|(_, life)| !life.is_alive());
     for (entity, _) in need_despawn {
         commands.despawn_recursive(entity);
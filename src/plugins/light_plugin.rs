@@ -10,5 +10,14 @@ impl Plugin for LightPlugin {
 }
 
 fn setup_ambient_light_system(mut ambient_light: ResMut<AmbientLight>) {
-    ambient_light.color = Color::rgba(0.8, 0.0, 0.2, 1.0);
+    ambient_light.color = ambient_color(true);
+}
+
+/// The arena gets a lot darker when the hero switches off the headlights to sneak past enemies.
+pub fn ambient_color(head_lights_on: bool) -> Color {
+    if head_lights_on {
+        Color::rgba(0.8, 0.0, 0.2, 1.0)
+    } else {
+        Color::rgba(0.15, 0.0, 0.04, 1.0)
+    }
 }
@@ -1,21 +1,86 @@
-pub(crate) use arena_plugin::ArenaPlugin;
-pub(crate) use camera_plugin::CameraPlugin;
-pub(crate) use game_plugin::GamePlugin;
-pub(crate) use gun_tower_plugin::GunTowerPlugin;
-pub(crate) use life_cycle_plugin::LifeCyclePlugin;
-pub(crate) use light_plugin::LightPlugin;
-pub(crate) use player_input_plugin::PlayerInputPlugin;
-pub(crate) use player_movement_plugin::PlayerMovementPlugin;
-pub(crate) use player_render_plugin::PlayerRenderPlugin;
-pub(crate) use projectile_plugin::ProjectilePlugin;
+use bevy::{app::PluginGroupBuilder, prelude::*};
 
+pub use app_state_plugin::{AppState, AppStatePlugin, MenuPlugin};
+pub use arena_plugin::ArenaPlugin;
+pub use camera_plugin::{CameraMode, CameraPlugin};
+pub use explosion_plugin::{ExplosionPlugin, ExplosionRenderPlugin};
+pub use game_plugin::{
+    asset_path, pbr_components, GameAssets, GameCameras, GamePlugin, GameRender, GameTime,
+    LevelResources,
+};
+pub use gun_tower_plugin::{GunTowerPlugin, GunTowerRenderPlugin};
+pub use headless_plugin::{HeadlessPlugin, TICK_SECONDS};
+pub use hero_lives_plugin::HeroLivesPlugin;
+pub use hero_plugin::{HeroPlugin, HERO_HOVER_HEIGHT};
+pub use life_cycle_plugin::LifeCyclePlugin;
+pub use light_plugin::LightPlugin;
+pub use physics_plugin::PhysicsPlugin;
+pub use player_input_plugin::PlayerInputPlugin;
+pub use player_movement_plugin::PlayerMovementPlugin;
+pub use player_render_plugin::PlayerRenderPlugin;
+pub use projectile_plugin::{ProjectilePlugin, ProjectileRenderPlugin};
+
+mod app_state_plugin;
 mod arena_plugin;
 mod camera_plugin;
+mod explosion_plugin;
 mod game_plugin;
 mod gun_tower_plugin;
+mod headless_plugin;
+mod hero_lives_plugin;
+mod hero_plugin;
 mod life_cycle_plugin;
 mod light_plugin;
+mod physics_plugin;
 mod player_input_plugin;
 mod player_movement_plugin;
 mod player_render_plugin;
 mod projectile_plugin;
+
+/// Plugins advancing the game, they neither render nor load assets so they also run headless.
+/// They expect the [GamePlugin] and an [AppStatePlugin] to be added first.
+pub struct SimulationPlugins;
+
+impl PluginGroup for SimulationPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(LifeCyclePlugin)
+            .add(HeroPlugin)
+            .add(HeroLivesPlugin)
+            .add(PlayerInputPlugin)
+            .add(PlayerMovementPlugin)
+            .add(PhysicsPlugin)
+            .add(ProjectilePlugin)
+            .add(GunTowerPlugin)
+            .add(ExplosionPlugin);
+    }
+}
+
+/// Plugins showing the simulation in a window along with the menus that drive it.
+pub struct RenderPlugins;
+
+impl PluginGroup for RenderPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(LightPlugin)
+            .add(MenuPlugin)
+            .add(CameraPlugin)
+            .add(ArenaPlugin)
+            .add(PlayerRenderPlugin)
+            .add(GunTowerRenderPlugin)
+            .add(ProjectileRenderPlugin)
+            .add(ExplosionRenderPlugin);
+    }
+}
+
+/// The whole game starting at the main menu, add it after the [GamePlugin] and before bevy's
+/// `DefaultPlugins` so the window picks up the settings.
+pub struct BatufoPlugins;
+
+impl PluginGroup for BatufoPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group.add(AppStatePlugin::default());
+        SimulationPlugins.build(group);
+        RenderPlugins.build(group);
+    }
+}
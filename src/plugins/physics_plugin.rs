@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::Tilepath,
+    ecs::{
+        components::{Knockback, Mass, MovementState, OrthogonalMovement, Velocity},
+        events::ImpulseEvent,
+    },
+    engine::physics::reflect_off_walls,
+};
+
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::{GameRender, GameTime},
+};
+
+/// Fraction of the speed kept when bouncing off a wall
+const WALL_RESTITUTION: f32 = 0.5;
+
+#[derive(Default)]
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<ImpulseEvent>()
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, apply_impulses.system())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, wall_impacts.system())
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                integrate_knockback.system(),
+            );
+    }
+}
+
+/// Position and velocity in tilemap units as rows grow towards negative z.
+fn to_tilemap(translation: &Vec3, velocity: &Vec3, tile_size: f32) -> ((f32, f32), (f32, f32)) {
+    (
+        (translation.x / tile_size, -translation.z / tile_size),
+        (velocity.x / tile_size, -velocity.z / tile_size),
+    )
+}
+
+fn apply_impulses(
+    mut event_reader: Local<EventReader<ImpulseEvent>>,
+    impulse_events: Res<Events<ImpulseEvent>>,
+    mut knockback_query: Query<(&mut Knockback, Option<&Mass>, Option<&OrthogonalMovement>)>,
+) {
+    for event in event_reader.iter(&impulse_events) {
+        if let Ok((mut knockback, mass, movement)) = knockback_query.get_mut(event.entity) {
+            // rolling towers are anchored to the tile edge they are tipping over
+            if let Some(OrthogonalMovement {
+                state: MovementState::Moving(_),
+                ..
+            }) = movement
+            {
+                continue;
+            }
+            let mass = mass.map_or(1.0, |mass| mass.0);
+            knockback.apply_impulse(event.impulse, mass);
+        }
+    }
+}
+
+/// Bounces entities that are about to fly into a wall back off of it.
+fn wall_impacts(
+    game_time: Res<GameTime>,
+    game_render: Res<GameRender>,
+    tilepath: Res<Tilepath>,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &mut Knockback,
+        Option<&mut Velocity>,
+        Option<&Mass>,
+    )>,
+    mut impulse_events: ResMut<Events<ImpulseEvent>>,
+) {
+    let dt = game_time.delta_seconds();
+    let tile_size = game_render.tile_size as f32;
+    for (entity, transform, mut knockback, velocity, mass) in query.iter_mut() {
+        let own_velocity = velocity.as_ref().map_or(Vec3::zero(), |v| v.0);
+        // the same move integrate_knockback and the player movement make this frame
+        let moving_by = knockback.displacement(dt) + own_velocity;
+        let (position, move_by) = to_tilemap(&transform.translation, &moving_by, tile_size);
+        let reflected = reflect_off_walls(position, move_by, |x, y| !tilepath.is_valid_at(x, y));
+        if let Some((x, y)) = reflected {
+            // flip the velocity along the axes the move was reflected on
+            let flip =
+                |reflected: f32, moved: f32| if reflected * moved < 0.0 { -1.0 } else { 1.0 };
+            let current = knockback.velocity + own_velocity;
+            let bounce = Vec3::new(
+                current.x * flip(x, move_by.0),
+                0.0,
+                current.z * flip(y, move_by.1),
+            ) * WALL_RESTITUTION;
+            knockback.velocity = Vec3::zero();
+            if let Some(mut velocity) = velocity {
+                velocity.0 = Vec3::zero();
+            }
+            let mass = mass.map_or(1.0, |mass| mass.0);
+            impulse_events.send(ImpulseEvent {
+                entity,
+                impulse: bounce * mass,
+            });
+        }
+    }
+}
+
+fn integrate_knockback(
+    game_time: Res<GameTime>,
+    mut query: Query<(&mut Transform, &mut Knockback)>,
+) {
+    let dt = game_time.delta_seconds();
+    for (mut transform, mut knockback) in query.iter_mut() {
+        if knockback.is_moving() {
+            transform.translation += knockback.step(dt);
+        }
+    }
+}
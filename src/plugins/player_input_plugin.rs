@@ -1,14 +1,31 @@
 use std::f32::EPSILON;
 
 use bevy::{
+    app::AppExit,
     input::{keyboard::KeyCode, mouse::MouseMotion, Input},
     prelude::*,
-    render::camera::PerspectiveProjection,
 };
 
 use crate::{
-    ecs::components::{HeadLights, Hero, HeroHull, Velocity},
-    engine::physics::{perp_vector_for_rotation_y, vector_for_rotation_y},
+    ecs::{
+        components::{
+            normalize_angle, BombBag, Faction, HeadLights, Hero, ProjectileSpawner, Velocity,
+            WeaponKind, Wreck,
+        },
+        events::{BombDroppedEvent, HitscanRequestedEvent, ProjectileRequestedEvent},
+        resources::{
+            recorded_keys, ControlSettings, InputFrame, InputRecorder, InputReplay,
+            PositionConverter,
+        },
+    },
+    engine::physics::{
+        angle_for_rotation_y, perp_vector_for_rotation_y, perpendicular, vector_for_rotation_y,
+    },
+};
+
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    projectile_plugin::fire_weapon,
 };
 
 #[derive(Default)]
@@ -16,17 +33,58 @@ pub struct PlayerInputPlugin;
 
 impl Plugin for PlayerInputPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(velocity_input_system.system())
-            .add_system(light_input_system.system())
-            .add_system(yaw_input_system.system());
+        // replayed input has to be in place before the systems below read it
+        app.on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            replay_input_system.system(),
+        )
+        .on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            velocity_input_system.system(),
+        )
+        .on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            light_input_system.system(),
+        )
+        .on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            weapon_input_system.system(),
+        )
+        .on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            bomb_input_system.system(),
+        )
+        .on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            yaw_input_system.system(),
+        )
+        .on_state_update(
+            APP_STATE_STAGE,
+            AppState::Playing,
+            record_input_system.system(),
+        )
+        .on_state_enter(APP_STATE_STAGE, AppState::Loading, stop_recording.system())
+        .on_state_enter(APP_STATE_STAGE, AppState::GameOver, stop_recording.system())
+        .on_state_enter(
+            APP_STATE_STAGE,
+            AppState::LevelComplete,
+            stop_recording.system(),
+        );
     }
 }
 
 fn velocity_input_system(
     keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Velocity, &Transform), With<Hero>>,
+    controls: Res<ControlSettings>,
+    mut query: Query<(&mut Velocity, &Transform), (With<Hero>, Without<Wreck>)>,
 ) {
-    let dv = 0.004;
+    let dv = controls.acceleration;
     if let Some((mut velocity, Transform { rotation, .. })) = query.iter_mut().next() {
         if keyboard_input.pressed(KeyCode::W) {
             let Vec3 { x, z, .. } = vector_for_rotation_y(rotation);
@@ -43,7 +101,7 @@ fn velocity_input_system(
             velocity.0.x += dv * x;
         } else {
             // stabilize perpendicular movement
-            let stability = 0.3;
+            let stability = controls.strafe_stability;
             if z.abs() > x.abs() {
                 let perp_vel = velocity.0.z * z;
                 if perp_vel > EPSILON {
@@ -65,30 +123,80 @@ fn velocity_input_system(
 
 fn light_input_system(
     keyboard_input: Res<Input<KeyCode>>,
-    mut lights_query: Query<(&mut Light, &mut HeadLights)>,
-    mut perspective_query: Query<&mut PerspectiveProjection>,
+    mut head_lights_query: Query<&mut HeadLights, With<Hero>>,
 ) {
-    let mut head_lights_on: bool = false;
     if keyboard_input.just_pressed(KeyCode::L) {
-        for (mut light, mut head_lights) in lights_query.iter_mut() {
+        for mut head_lights in head_lights_query.iter_mut() {
             head_lights.0 = !head_lights.0;
-            head_lights_on = head_lights.0;
+        }
+    }
+}
 
-            if head_lights_on {
-                light.color = Color::WHITE;
-            } else {
-                light.color = Color::BLACK;
-            }
+/// Weapons the hero switches between with the number keys.
+fn hero_weapon(key: KeyCode) -> Option<WeaponKind> {
+    match key {
+        KeyCode::Key1 => Some(WeaponKind::Single),
+        KeyCode::Key2 => Some(WeaponKind::Spread {
+            count: 5,
+            arc: 40.0,
+        }),
+        KeyCode::Key3 => Some(WeaponKind::Burst {
+            count: 3,
+            interval: 0.08,
+        }),
+        KeyCode::Key4 => Some(WeaponKind::Laser),
+        KeyCode::Key5 => Some(WeaponKind::Homing { turn_rate: 120.0 }),
+        _ => None,
+    }
+}
+
+fn weapon_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    converter: Res<PositionConverter>,
+    mut query: Query<(&Transform, &mut ProjectileSpawner), (With<Hero>, Without<Wreck>)>,
+    mut projectile_events: ResMut<Events<ProjectileRequestedEvent>>,
+    mut hitscan_events: ResMut<Events<HitscanRequestedEvent>>,
+) {
+    if let Some((transform, mut spawner)) = query.iter_mut().next() {
+        if let Some(weapon) = keyboard_input
+            .get_just_pressed()
+            .find_map(|key| hero_weapon(*key))
+        {
+            spawner.weapon = weapon;
+        }
+        if !keyboard_input.pressed(KeyCode::Space) || !spawner.is_ready() {
+            return;
+        }
+        if let Some(origin) = converter.tile_from_translation(&transform.translation) {
+            // the hero faces along negative z when not rotated, which is 90 degrees on the tilemap
+            let direction =
+                normalize_angle(perpendicular(angle_for_rotation_y(&transform.rotation)));
+            fire_weapon(
+                &mut spawner,
+                &origin,
+                direction,
+                Faction::Hero,
+                &mut projectile_events,
+                &mut hitscan_events,
+            );
         }
+    }
+}
 
-        // TODO: Currently has no effect, may need to update camera orthographic projection
-        // directly
-        for mut persp in perspective_query.iter_mut() {
-            if head_lights_on {
-                persp.far = 5000.0;
-            } else {
-                persp.far = 500.0;
-            }
+fn bomb_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<(&Transform, &mut BombBag), (With<Hero>, Without<Wreck>)>,
+    mut events: ResMut<Events<BombDroppedEvent>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::B) {
+        return;
+    }
+    if let Some((transform, mut bomb_bag)) = query.iter_mut().next() {
+        if bomb_bag.bombs > 0 {
+            bomb_bag.bombs -= 1;
+            events.send(BombDroppedEvent {
+                position: transform.translation,
+            });
         }
     }
 }
@@ -100,11 +208,12 @@ struct MouseState {
 
 fn yaw_input_system(
     mut state: Local<MouseState>,
+    controls: Res<ControlSettings>,
     mouse_button_input: Res<Input<MouseButton>>,
     mouse_motion_events: Res<Events<MouseMotion>>,
-    mut transform_query: Query<&mut Transform, With<Hero>>,
+    mut transform_query: Query<&mut Transform, (With<Hero>, Without<Wreck>)>,
 ) {
-    let rot_factor = 0.01;
+    let rot_factor = controls.mouse_sensitivity;
     if mouse_button_input.pressed(MouseButton::Left) {
         for event in state.mouse_motion_event_reader.iter(&mouse_motion_events) {
             let dx = event.delta.x;
@@ -114,3 +223,79 @@ fn yaw_input_system(
         }
     }
 }
+
+/// Presses and releases the keys and mouse button of the next replayed tick and quits once
+/// the replay is over. Input from the keyboard and mouse mixes in while replaying.
+fn replay_input_system(
+    mut replay: ResMut<InputReplay>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_button_input: ResMut<Input<MouseButton>>,
+    mut mouse_motion_events: ResMut<Events<MouseMotion>>,
+    mut exit_events: ResMut<Events<AppExit>>,
+) {
+    let frame = match replay.next_frame() {
+        Some(frame) => frame.clone(),
+        None => return,
+    };
+    for (key, name) in recorded_keys() {
+        let held = frame.keys.iter().any(|held| held == name);
+        if held && !keyboard_input.pressed(key) {
+            keyboard_input.press(key);
+        } else if !held && keyboard_input.pressed(key) {
+            keyboard_input.release(key);
+        }
+    }
+    if frame.turning {
+        mouse_button_input.press(MouseButton::Left);
+        mouse_motion_events.send(MouseMotion {
+            delta: Vec2::new(frame.mouse_dx, 0.0),
+        });
+    } else {
+        mouse_button_input.release(MouseButton::Left);
+    }
+    if !replay.is_replaying() {
+        info!("replay finished");
+        exit_events.send(AppExit);
+    }
+}
+
+#[derive(Default)]
+struct RecordState {
+    mouse_motion_event_reader: EventReader<MouseMotion>,
+}
+
+fn record_input_system(
+    mut state: Local<RecordState>,
+    mut recorder: ResMut<InputRecorder>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    mouse_motion_events: Res<Events<MouseMotion>>,
+) {
+    let mouse_dx = state
+        .mouse_motion_event_reader
+        .iter(&mouse_motion_events)
+        .map(|event| event.delta.x)
+        .sum();
+    if !recorder.is_recording() {
+        return;
+    }
+    let frame = InputFrame {
+        keys: recorded_keys()
+            .filter(|(key, _)| keyboard_input.pressed(*key))
+            .map(|(_, name)| name.to_string())
+            .collect(),
+        turning: mouse_button_input.pressed(MouseButton::Left),
+        mouse_dx,
+    };
+    if let Err(err) = recorder.record(&frame) {
+        error!("unable to record input: {}", err);
+        recorder.stop();
+    }
+}
+
+/// A recording covers one play of a level, it ends once the level is left or loaded again.
+fn stop_recording(mut recorder: ResMut<InputRecorder>) {
+    if recorder.ticks() > 0 {
+        recorder.stop();
+    }
+}
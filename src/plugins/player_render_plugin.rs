@@ -1,66 +1,157 @@
-use std::path::PathBuf;
+use std::{f32::EPSILON, path::PathBuf};
 
-use bevy::prelude::*;
+use bevy::{pbr::AmbientLight, prelude::*, render::camera::PerspectiveProjection};
 
 use crate::{
-    arena::Arena,
-    ecs::components::{HeadLights, Hero, HeroHull, LifeCycle, Velocity},
+    ecs::components::{HeadLights, Hero, HeroHull, Invulnerable, Velocity},
+    engine::physics::perp_vector_for_rotation_y,
 };
 
-use super::game_plugin::{GameAssets, GameRender};
+use super::{game_plugin::GameAssets, light_plugin::ambient_color};
+
+/// Marks the light shining ahead of the hero while its head lights are on.
+struct HeroLight;
 
 #[derive(Default)]
 pub struct PlayerRenderPlugin;
 
 impl Plugin for PlayerRenderPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(setup_hero.system());
+        app.add_system(setup_hero_visuals.system())
+            .add_system(player_roll.system())
+            .add_system(show_head_lights.system())
+            .add_system(blink_invulnerable.system());
     }
 }
 
-fn setup_hero(
+/// Adds the ship model and its light to a hero spawned by the simulation.
+fn setup_hero_visuals(
     commands: &mut Commands,
     game_assets: Res<GameAssets>,
-    game_render: Res<GameRender>,
-    arena: Res<Arena>,
     asset_server: Res<AssetServer>,
+    hero_query: Query<Entity, (With<Hero>, Without<Children>)>,
 ) {
-    let mut pos = arena.player.to_world_position(game_render.tile_size);
-    let size = game_render.tile_size as f32;
-    pos.y = size * 0.2;
-
-    commands
-        .spawn((
-            {
-                let mut transform: Transform = pos.into();
-                transform.scale = transform.scale * 0.18;
-                transform
-            },
-            GlobalTransform::default(),
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn((Transform::default(), GlobalTransform::default()))
-                .with_children(|parent| {
-                    parent.spawn_scene(asset_server.load(PathBuf::from(&game_assets.hero.path)));
-                })
-                .with(HeroHull);
-        })
-        .with_children(|parent| {
-            parent
-                .spawn(LightBundle {
-                    light: Light {
-                        color: Color::rgb_linear(1.0, 1.0, 0.8),
-                        depth: 0.1..5.0,
-                        fov: f32::to_radians(15.0),
+    for entity in hero_query.iter() {
+        commands.set_current_entity(entity);
+        commands
+            .with_children(|parent| {
+                parent
+                    .spawn((Transform::default(), GlobalTransform::default()))
+                    .with_children(|parent| {
+                        parent
+                            .spawn_scene(asset_server.load(PathBuf::from(&game_assets.hero.path)));
+                    })
+                    .with(HeroHull);
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn(LightBundle {
+                        light: Light {
+                            color: Color::rgb_linear(1.0, 1.0, 0.8),
+                            depth: 0.1..5.0,
+                            fov: f32::to_radians(15.0),
+                            ..Default::default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(0.0, 0.0, -0.31)),
                         ..Default::default()
-                    },
-                    transform: Transform::from_translation(Vec3::new(0.0, 0.0, -0.31)),
-                    ..Default::default()
-                })
-                .with(HeadLights(true));
-        })
-        .with(Hero::default())
-        .with(LifeCycle::default())
-        .with(Velocity::default());
+                    })
+                    .with(HeroLight);
+            });
+    }
+}
+
+fn rot_for_perp_velocity(perp_vel: f32) -> f32 {
+    let max_rot = f32::to_radians(30.0);
+    let rot_factor = 1.5;
+    if perp_vel > EPSILON {
+        (-perp_vel * rot_factor).max(-max_rot)
+    } else if perp_vel < EPSILON {
+        (-perp_vel * rot_factor).min(max_rot)
+    } else {
+        0.0
+    }
+}
+
+fn player_roll(
+    hero_query: Query<(&Velocity, &Transform), With<Hero>>,
+    mut hull_query: Query<&mut Transform, With<HeroHull>>,
+) {
+    if let Some((velocity, Transform { rotation, .. })) = hero_query.iter().next() {
+        if let Some(mut hull_transform) = hull_query.iter_mut().next() {
+            let current_rot = hull_transform.rotation.z;
+            let Vec3 { x, z, .. } = perp_vector_for_rotation_y(rotation);
+            if z.abs() > x.abs() {
+                // facing along z-axis -> rotate around x-axis
+                let rot = rot_for_perp_velocity(velocity.0.z * z);
+                hull_transform.rotate(Quat::from_rotation_z(rot - current_rot));
+            } else {
+                // facing along x-axis -> rotate around z-axis
+                let rot = rot_for_perp_velocity(velocity.0.x * x);
+                hull_transform.rotate(Quat::from_rotation_z(rot - current_rot));
+            }
+        }
+    }
+}
+
+fn show_head_lights(
+    hero_query: Query<&HeadLights, (With<Hero>, Mutated<HeadLights>)>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut lights_query: Query<&mut Light, With<HeroLight>>,
+    mut perspective_query: Query<&mut PerspectiveProjection>,
+) {
+    if let Some(head_lights) = hero_query.iter().next() {
+        let head_lights_on = head_lights.0;
+        for mut light in lights_query.iter_mut() {
+            if head_lights_on {
+                light.color = Color::WHITE;
+            } else {
+                light.color = Color::BLACK;
+            }
+        }
+        ambient_light.color = ambient_color(head_lights_on);
+
+        // TODO: Currently has no effect, may need to update camera orthographic projection
+        // directly
+        for mut persp in perspective_query.iter_mut() {
+            if head_lights_on {
+                persp.far = 5000.0;
+            } else {
+                persp.far = 500.0;
+            }
+        }
+    }
+}
+
+/// Blinks the hero while it is invulnerable and shows it again afterwards.
+fn blink_invulnerable(
+    mut was_blinking: Local<bool>,
+    hero_query: Query<(Entity, Option<&Invulnerable>), With<Hero>>,
+    children_query: Query<&Children>,
+    mut visible_query: Query<&mut Visible>,
+) {
+    for (entity, invulnerable) in hero_query.iter() {
+        if invulnerable.is_none() && !*was_blinking {
+            continue;
+        }
+        *was_blinking = invulnerable.is_some();
+        let is_shown = invulnerable.map_or(true, |invulnerable| invulnerable.is_shown());
+        set_visible(entity, is_shown, &children_query, &mut visible_query);
+    }
+}
+
+/// The hero is made of the meshes of its scene which are nested a few levels deep.
+fn set_visible(
+    entity: Entity,
+    is_visible: bool,
+    children_query: &Query<&Children>,
+    visible_query: &mut Query<&mut Visible>,
+) {
+    if let Ok(mut visible) = visible_query.get_mut(entity) {
+        visible.is_visible = is_visible;
+    }
+    if let Ok(children) = children_query.get(entity) {
+        for child in children.iter() {
+            set_visible(*child, is_visible, children_query, visible_query);
+        }
+    }
 }
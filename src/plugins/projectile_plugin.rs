@@ -1,109 +1,317 @@
 use bevy::prelude::*;
 
 use crate::{
+    arena::Tilepath,
     ecs::{
-        components::{Hero, LifeCycle, Projectile, ProjectileSpawner, Velocity},
-        events::ProjectileRequestedEvent,
+        components::{
+            steer_towards, Explosive, Faction, Fading, Homing, Invulnerable, LevelEntity,
+            LifeCycle, Projectile, ProjectileSpawner, Velocity,
+        },
+        events::{
+            HitscanRequestedEvent, ImpulseEvent, LaserFiredEvent, LoadLevelEvent,
+            ProjectileRequestedEvent,
+        },
+        resources::{PositionConverter, ProjectileKind, ProjectilePool, ProjectileVisuals, Sniper},
     },
-    engine::WorldPosition,
+    engine::{physics::reflect_off_walls, TilePosition, WorldPosition},
 };
 
-use super::game_plugin::GameRender;
+use super::{
+    app_state_plugin::{AppState, APP_STATE_STAGE},
+    game_plugin::{pbr_components, GameRender, GameTime},
+};
+
+/// Portion of its momentum a projectile transfers onto what it hits
+const PROJECTILE_IMPULSE_FACTOR: f32 = 0.5;
+/// Seconds a laser beam stays visible after firing
+const LASER_BEAM_SECONDS: f32 = 0.1;
 
 #[derive(Default)]
 pub struct ProjectilePlugin;
 
 impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system_to_stage(stage::PRE_UPDATE, update_spawners.system())
-            .add_system_to_stage(stage::POST_UPDATE, reset_spawners.system())
+        app.add_resource(ProjectilePool::default())
+            .on_state_update(APP_STATE_STAGE, AppState::Playing, update_spawners.system())
             .add_event::<ProjectileRequestedEvent>()
-            .add_system(spawn_projectile.system())
-            .add_system(detect_collision.system())
-            .add_system(destroy_out_of_range_projectile.system());
+            .add_event::<HitscanRequestedEvent>()
+            .add_event::<LaserFiredEvent>()
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                spawn_projectile.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                steer_homing_projectiles.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                resolve_hitscans.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                ricochet_off_walls.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                detect_collision.system(),
+            )
+            .on_state_update(
+                APP_STATE_STAGE,
+                AppState::Playing,
+                destroy_out_of_range_projectile.system(),
+            )
+            .add_system_to_stage(stage::POST_UPDATE, recycle_projectiles.system());
     }
 }
 
-fn update_spawners(mut spawner_query: Query<&mut ProjectileSpawner>) {
-    for mut spawner in spawner_query.iter_mut() {
-        if spawner.ticks_until_reloaded > 0 {
-            spawner.ticks_until_reloaded -= 1;
-        }
+#[derive(Default)]
+pub struct ProjectileRenderPlugin;
+
+impl Plugin for ProjectileRenderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup_projectile_visuals.system())
+            .add_system(show_laser_beams.system())
+            .add_system_to_stage(stage::POST_UPDATE, show_projectiles.system())
+            .add_system_to_stage(stage::POST_UPDATE, hide_destroyed_projectiles.system());
     }
 }
 
-fn reset_spawners(mut spawner_query: Query<&mut ProjectileSpawner>) {
-    for mut spawner in spawner_query.iter_mut() {
-        if spawner.ticks_until_reloaded == 0 {
-            spawner.ticks_until_reloaded = spawner.ticks_to_reload;
+/// Fires the weapon of the spawner, emitting projectile or hitscan requests for each shot.
+/// The spawner then cools down or reloads if its magazine is empty.
+pub fn fire_weapon(
+    spawner: &mut ProjectileSpawner,
+    origin: &TilePosition,
+    direction: f32,
+    faction: Faction,
+    projectile_events: &mut Events<ProjectileRequestedEvent>,
+    hitscan_events: &mut Events<HitscanRequestedEvent>,
+) {
+    for shot in spawner.weapon.shots(direction) {
+        if spawner.weapon.is_hitscan() {
+            hitscan_events.send(HitscanRequestedEvent {
+                origin: origin.clone(),
+                direction: shot.direction,
+                range: spawner.range.sqrt(),
+                health_damage: spawner.health_damage,
+                faction,
+            });
+        } else {
+            projectile_events.send(ProjectileRequestedEvent {
+                homing_turn_rate: spawner.weapon.homing_turn_rate(),
+                delay: shot.delay,
+                bounces: spawner.bounces,
+                ..ProjectileRequestedEvent::new(
+                    origin.clone(),
+                    shot.direction,
+                    spawner.projectile_speed,
+                    spawner.range,
+                    spawner.health_damage,
+                    faction,
+                )
+            });
         }
     }
+    spawner.fire();
 }
 
-fn spawn_projectile(
+fn setup_projectile_visuals(
     commands: &mut Commands,
+    game_render: Res<GameRender>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let visuals = ProjectileVisuals::new(game_render.tile_size as f32, &mut meshes, &mut materials);
+    commands.insert_resource(visuals);
+}
+
+fn update_spawners(time: Res<GameTime>, mut spawner_query: Query<&mut ProjectileSpawner>) {
+    for mut spawner in spawner_query.iter_mut() {
+        spawner.tick(time.delta_seconds());
+    }
+}
+
+fn spawn_projectile(
+    commands: &mut Commands,
+    mut pool: ResMut<ProjectilePool>,
     mut event_reader: Local<EventReader<ProjectileRequestedEvent>>,
+    mut delayed: Local<Vec<ProjectileRequestedEvent>>,
+    time: Res<GameTime>,
     game_render: Res<GameRender>,
     projectile_requested_events: Res<Events<ProjectileRequestedEvent>>,
+    mut load_level_reader: Local<EventReader<LoadLevelEvent>>,
+    load_level_events: Res<Events<LoadLevelEvent>>,
 ) {
-    let size = game_render.tile_size as f32 / 5.0;
-    let mag = 0.2_f32;
-
+    // shots of a burst still waiting to be fired belong to the level that was replaced
+    if load_level_reader.iter(&load_level_events).next().is_some() {
+        delayed.clear();
+    }
     for event in event_reader.iter(&projectile_requested_events) {
+        delayed.push(event.clone());
+    }
+    let (due, mut waiting): (Vec<_>, Vec<_>) =
+        delayed.drain(..).partition(|event| event.delay <= 0.0);
+    for event in waiting.iter_mut() {
+        event.delay -= time.delta_seconds();
+    }
+    *delayed = waiting;
+
+    for event in due {
+        let mag = event.velocity * game_render.tile_size as f32;
         let mut velocity = Velocity::default();
         velocity.0.z = -mag * event.direction.sin();
         velocity.0.x = mag * event.direction.cos();
 
         let tp = &event.origin;
         let pos = tp.to_world_position(game_render.tile_size);
+        let transform: Transform = (&pos).into();
 
-        // TODO(thlorenz): create the entire bundle or at least mesh and material once during setup
-        // and add to resources
-        commands
-            .spawn(PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Box::new(size, size, size))),
-                material: {
-                    let material = materials.add(Color::rgb(0.3, 0.4, 0.9).into());
-                    material
-                },
-                transform: {
-                    let transform: Transform = (&pos).into();
-                    transform
+        let entity = match pool.take() {
+            Some(entity) => entity,
+            None => commands
+                .spawn((Transform::default(), GlobalTransform::default()))
+                .with(LevelEntity)
+                .current_entity()
+                .expect("spawned projectile"),
+        };
+        commands.insert(
+            entity,
+            (
+                transform,
+                Projectile {
+                    faction: event.faction,
+                    range: event.range * game_render.tile_size as f32,
+                    distance_traveled: 0.0,
+                    bounces_left: event.bounces,
+                    health_damage: event.health_damage,
                 },
-                ..Default::default()
-            })
-            .with(Projectile {
-                origin: (&pos).into(),
-                range: event.range * game_render.tile_size as f32,
-                health_damage: event.health_damage,
+                LifeCycle::default(),
+                velocity,
+            ),
+        );
+        if let Some(turn_rate) = event.homing_turn_rate {
+            commands.insert(entity, (Homing { turn_rate }, Explosive::missile()));
+        }
+    }
+}
+
+fn steer_homing_projectiles(
+    time: Res<GameTime>,
+    mut projectile_query: Query<(&mut Velocity, &Transform, &Projectile, &Homing)>,
+    target_query: Query<(&Transform, &Faction)>,
+) {
+    for (mut velocity, transform, projectile, homing) in projectile_query.iter_mut() {
+        let closest_target = target_query
+            .iter()
+            .filter(|(_, faction)| **faction != projectile.faction)
+            .map(|(target, _)| target.translation - transform.translation)
+            .min_by(|a, b| {
+                a.length_squared()
+                    .partial_cmp(&b.length_squared())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        if let Some(to_target) = closest_target {
+            let max_turn = homing.turn_rate * time.delta_seconds();
+            velocity.0 = steer_towards(velocity.0, to_target, max_turn);
+        }
+    }
+}
+
+fn resolve_hitscans(
+    mut event_reader: Local<EventReader<HitscanRequestedEvent>>,
+    hitscan_requested_events: Res<Events<HitscanRequestedEvent>>,
+    game_render: Res<GameRender>,
+    sniper: Res<Sniper>,
+    tilepath: Res<Tilepath>,
+    converter: Res<PositionConverter>,
+    mut target_query: Query<(Entity, &mut LifeCycle, &Transform, &Faction), Without<Invulnerable>>,
+    mut laser_events: ResMut<Events<LaserFiredEvent>>,
+) {
+    for event in event_reader.iter(&hitscan_requested_events) {
+        let (entities, tiles): (Vec<Entity>, Vec<TilePosition>) = target_query
+            .iter_mut()
+            .filter(|(_, life, _, faction)| **faction != event.faction && life.is_alive())
+            .filter_map(|(entity, _, transform, _)| {
+                let tile = converter.tile_from_translation(&transform.translation)?;
+                Some((entity, tile))
             })
-            .with(LifeCycle::default())
-            .with(velocity);
+            .unzip();
+
+        let hit = sniper.cast_laser(&tilepath, &event.origin, event.direction, &tiles);
+        let distance = hit
+            .as_ref()
+            .map_or(event.range, |hit| hit.distance.min(event.range));
+        let target = hit
+            .filter(|hit| hit.distance <= event.range)
+            .and_then(|hit| hit.target_idx);
+        if let Some(idx) = target {
+            if let Ok(mut life) = target_query.get_component_mut::<LifeCycle>(entities[idx]) {
+                life.deduct(event.health_damage);
+            }
+        }
+
+        laser_events.send(LaserFiredEvent {
+            start: converter.translation_from_tile(&event.origin),
+            direction: event.direction,
+            length: distance * game_render.tile_size as f32,
+        });
+    }
+}
+
+fn ricochet_off_walls(
+    game_render: Res<GameRender>,
+    tilepath: Res<Tilepath>,
+    mut projectile_query: Query<(&mut Velocity, &mut LifeCycle, &mut Projectile, &Transform)>,
+) {
+    let tile_size = game_render.tile_size as f32;
+    for (mut velocity, mut life, mut projectile, transform) in projectile_query.iter_mut() {
+        // rows grow towards negative z
+        let position = (
+            transform.translation.x / tile_size,
+            -transform.translation.z / tile_size,
+        );
+        let move_by = (velocity.0.x / tile_size, -velocity.0.z / tile_size);
+        let reflected = reflect_off_walls(position, move_by, |x, y| !tilepath.is_valid_at(x, y));
+        match reflected {
+            None => {}
+            Some(_) if projectile.bounces_left == 0 => life.terminate(),
+            Some((x, y)) => {
+                projectile.bounces_left -= 1;
+                velocity.0.x = x * tile_size;
+                velocity.0.z = -y * tile_size;
+            }
+        }
     }
 }
 
 fn detect_collision(
     game_render: Res<GameRender>,
-    mut projectile_query: Query<(&mut LifeCycle, &Transform, &Projectile)>,
-    mut hero_query: Query<(&mut LifeCycle, &Transform), With<Hero>>,
+    mut projectile_query: Query<(&mut LifeCycle, &Transform, &Projectile, &Velocity)>,
+    mut target_query: Query<(Entity, &mut LifeCycle, &Transform, &Faction), Without<Invulnerable>>,
+    mut impulse_events: ResMut<Events<ImpulseEvent>>,
 ) {
-    // TODO(thlorenz): add TilePosition to Hero struct to calc it in one place once
-    if let Some((mut hero_life, transform)) = hero_query.iter_mut().next() {
-        let hero_tp = WorldPosition::from(transform).to_tile_position(game_render.tile_size);
-        let hero_tp = match hero_tp {
-            None => return,
-            Some(hero_tp) => hero_tp,
+    for (mut life, transform, projectile, velocity) in projectile_query.iter_mut() {
+        let tp = match WorldPosition::from(transform).to_tile_position(game_render.tile_size) {
+            None => continue,
+            Some(tp) => tp,
         };
-        for (mut life, transform, projectile) in projectile_query.iter_mut() {
-            let tp = match WorldPosition::from(transform).to_tile_position(game_render.tile_size) {
-                None => continue,
-                Some(tp) => tp,
-            };
-            if tp.is_same_tile(&hero_tp) {
-                hero_life.deduct(projectile.health_damage);
-                println!("hero deducted health, now {}", hero_life.health());
+        for (entity, mut target_life, target_transform, faction) in target_query.iter_mut() {
+            if *faction == projectile.faction || !life.is_alive() || !target_life.is_alive() {
+                continue;
+            }
+            let target_tp =
+                WorldPosition::from(target_transform).to_tile_position(game_render.tile_size);
+            if target_tp.map_or(false, |target_tp| tp.is_same_tile(&target_tp)) {
+                target_life.deduct(projectile.health_damage);
+                impulse_events.send(ImpulseEvent {
+                    entity,
+                    impulse: velocity.0 * PROJECTILE_IMPULSE_FACTOR,
+                });
                 life.terminate();
             }
         }
@@ -111,12 +319,89 @@ fn detect_collision(
 }
 
 fn destroy_out_of_range_projectile(
-    mut projectile_query: Query<(&mut LifeCycle, &Transform, &Projectile)>,
+    mut projectile_query: Query<(&mut LifeCycle, &mut Projectile, &Velocity)>,
 ) {
-    for (mut life, transform, projectile) in projectile_query.iter_mut() {
-        let distance = projectile.origin.distance_squared(transform.translation);
-        if distance >= projectile.range {
+    for (mut life, mut projectile, velocity) in projectile_query.iter_mut() {
+        projectile.distance_traveled += velocity.0.length();
+        if projectile.distance_traveled.powi(2) >= projectile.range {
             life.terminate();
         }
     }
 }
+
+/// Returns destroyed projectiles to the pool, despawning them once it is full.
+/// Explosives are detonated during the post update as well, before the removals are applied.
+fn recycle_projectiles(
+    commands: &mut Commands,
+    mut pool: ResMut<ProjectilePool>,
+    projectile_query: Query<(Entity, &LifeCycle, Option<&Homing>), With<Projectile>>,
+) {
+    for (entity, life, homing) in projectile_query.iter() {
+        if life.is_alive() {
+            continue;
+        }
+        if !pool.put(entity) {
+            commands.despawn_recursive(entity);
+            continue;
+        }
+        commands.remove::<(Projectile, LifeCycle, Velocity)>(entity);
+        if homing.is_some() {
+            commands.remove::<(Homing, Explosive)>(entity);
+        }
+    }
+}
+
+/// Shows projectiles spawned by the simulation, pooled projectiles are shown again when reused.
+fn show_projectiles(
+    commands: &mut Commands,
+    visuals: Res<ProjectileVisuals>,
+    projectile_query: Query<(Entity, &Projectile, Option<&Homing>), Added<Projectile>>,
+) {
+    for (entity, projectile, homing) in projectile_query.iter() {
+        let kind = match homing {
+            Some(_) => ProjectileKind::Missile(projectile.faction),
+            None => ProjectileKind::Bullet(projectile.faction),
+        };
+        let (mesh, material) = visuals.get(kind);
+        commands.insert(entity, pbr_components(mesh, material));
+    }
+}
+
+/// Destroyed projectiles stay hidden in the pool until they are reused.
+fn hide_destroyed_projectiles(
+    mut projectile_query: Query<(&LifeCycle, &mut Visible), With<Projectile>>,
+) {
+    for (life, mut visible) in projectile_query.iter_mut() {
+        if !life.is_alive() {
+            visible.is_visible = false;
+        }
+    }
+}
+
+/// Shows a beam from where the laser was fired to where it stopped.
+fn show_laser_beams(
+    commands: &mut Commands,
+    visuals: Res<ProjectileVisuals>,
+    mut event_reader: Local<EventReader<LaserFiredEvent>>,
+    laser_events: Res<Events<LaserFiredEvent>>,
+) {
+    for event in event_reader.iter(&laser_events) {
+        let delta = Vec3::new(event.direction.cos(), 0.0, -event.direction.sin()) * event.length;
+        let mut transform = Transform::from_translation(event.start + delta / 2.0);
+        transform.rotation = Quat::from_rotation_y(event.direction);
+        transform.scale = Vec3::new(event.length, 1.0, 1.0);
+        let (mesh, material) = visuals.get(ProjectileKind::Beam);
+        commands
+            .spawn(PbrBundle {
+                mesh,
+                material,
+                transform,
+                ..Default::default()
+            })
+            .with(Fading {
+                seconds_left: LASER_BEAM_SECONDS,
+            })
+            .with(LifeCycle::default())
+            .with(LevelEntity);
+    }
+}
@@ -0,0 +1,303 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, ElementState, InputPlugin},
+    prelude::*,
+};
+
+use batufo::{
+    arena::{Arena, WallThemes},
+    ecs::{
+        components::{Faction, Hero, LifeCycle, Lives, Projectile, Wreck},
+        events::LoadLevelEvent,
+        resources::{EnemyArchetypes, PositionConverter},
+    },
+    plugins::{
+        AppState, AppStatePlugin, GamePlugin, GameRender, HeadlessPlugin, LevelResources,
+        SimulationPlugins,
+    },
+};
+
+/// Runs the simulation of a level headless, advancing it one fixed tick per update.
+struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    /// Builds the simulation for a terrain, enemies are placed via its `[spawns]` section.
+    fn new(terrain: &str) -> Self {
+        Self::with_game(terrain, GamePlugin::default())
+    }
+
+    fn with_game(terrain: &str, game: GamePlugin) -> Self {
+        let mut builder = App::build();
+        builder
+            .add_plugin(game)
+            .add_plugin(HeadlessPlugin::default())
+            .add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .add_plugin(AppStatePlugin {
+                initial: AppState::Loading,
+            })
+            .add_plugins(SimulationPlugins);
+
+        let level = {
+            let resources = &builder.app.resources;
+            let tile_size = resources
+                .get::<GameRender>()
+                .expect("game render")
+                .tile_size;
+            let arena = Arena::from_terrain(terrain, tile_size).expect("valid terrain");
+            LevelResources::new(
+                arena,
+                tile_size,
+                &resources.get::<PositionConverter>().expect("converter"),
+                &resources.get::<EnemyArchetypes>().expect("archetypes"),
+                &resources.get::<WallThemes>().expect("wall themes"),
+            )
+            .expect("valid level")
+        };
+        builder
+            .add_resource(level.arena)
+            .add_resource(level.tile_path)
+            .add_resource(level.hierarchical_tile_path)
+            .add_resource(level.sniper);
+
+        Self { app: builder.app }
+    }
+
+    fn step(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ElementState::Pressed);
+    }
+
+    fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ElementState::Released);
+    }
+
+    fn send_key(&mut self, key: KeyCode, state: ElementState) {
+        self.app
+            .resources
+            .get_mut::<Events<KeyboardInput>>()
+            .expect("keyboard input events")
+            .send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key),
+                state,
+            });
+    }
+
+    fn load_level(&mut self, level: &str) {
+        self.app
+            .resources
+            .get_mut::<Events<LoadLevelEvent>>()
+            .expect("load level events")
+            .send(LoadLevelEvent {
+                level: level.to_string(),
+                keep_hero: true,
+            });
+    }
+
+    fn state(&self) -> AppState {
+        let state = self.app.resources.get::<State<AppState>>().expect("state");
+        *state.current()
+    }
+
+    fn tile(&self, transform: &Transform) -> (u32, u32) {
+        self.app
+            .resources
+            .get::<PositionConverter>()
+            .expect("converter")
+            .tile_from_translation(&transform.translation)
+            .expect("inside the arena")
+            .col_row()
+    }
+
+    fn hero_health(&self) -> u16 {
+        let mut hero_query = self.app.world.query_filtered::<&LifeCycle, With<Hero>>();
+        hero_query.next().expect("hero").health()
+    }
+
+    fn hero(&self) -> Entity {
+        let mut hero_query = self.app.world.query_filtered::<Entity, With<Hero>>();
+        hero_query.next().expect("hero")
+    }
+
+    fn hero_tile(&self) -> (u32, u32) {
+        let mut hero_query = self.app.world.query_filtered::<&Transform, With<Hero>>();
+        self.tile(hero_query.next().expect("hero"))
+    }
+
+    /// Health and tile of each enemy.
+    fn enemies(&self) -> Vec<(u16, (u32, u32))> {
+        self.app
+            .world
+            .query::<(&Faction, &LifeCycle, &Transform)>()
+            .filter(|(faction, _, _)| **faction == Faction::Enemy)
+            .map(|(_, life, transform)| (life.health(), self.tile(transform)))
+            .collect()
+    }
+
+    /// Projectiles in flight, not counting the ones waiting in the pool.
+    fn projectile_count(&self) -> usize {
+        self.app.world.query::<&Projectile>().count()
+    }
+}
+
+/// Gun tower facing down at the hero three tiles below it.
+const GUN_TOWER_ABOVE_HERO: &str = "
+=====
+=   =
+=   =
+=   =
+=   =
+= p =
+=====
+
+[spawns]
+gun-tower 2,4
+";
+
+#[test]
+fn loads_level() {
+    let mut simulation = Simulation::new(GUN_TOWER_ABOVE_HERO);
+    simulation.step(2);
+    assert_eq!(simulation.state(), AppState::Playing);
+    assert_eq!(simulation.hero_tile(), (2, 1));
+    assert_eq!(simulation.hero_health(), 100);
+    assert_eq!(simulation.enemies(), vec![(100, (2, 4))]);
+}
+
+#[test]
+fn hero_thrusts_forward() {
+    let mut simulation = Simulation::new(GUN_TOWER_ABOVE_HERO);
+    simulation.step(2);
+    simulation.press(KeyCode::W);
+    simulation.step(30);
+    simulation.release(KeyCode::W);
+    let (col, row) = simulation.hero_tile();
+    assert_eq!(col, 2, "keeps heading up");
+    assert!(row > 1, "moved up to row {}", row);
+}
+
+#[test]
+fn hero_projectiles_damage_tower() {
+    let mut simulation = Simulation::new(GUN_TOWER_ABOVE_HERO);
+    simulation.step(2);
+    simulation.press(KeyCode::Space);
+    simulation.step(5);
+    assert!(simulation.projectile_count() > 0, "fires projectiles");
+    simulation.step(55);
+    simulation.release(KeyCode::Space);
+    let (health, _) = simulation.enemies()[0];
+    assert!(health < 100, "tower health {}", health);
+}
+
+#[test]
+fn tower_projectiles_damage_hero() {
+    let mut simulation = Simulation::new(GUN_TOWER_ABOVE_HERO);
+    simulation.step(120);
+    assert!(
+        simulation.hero_health() < 100,
+        "hero health {}",
+        simulation.hero_health()
+    );
+}
+
+#[test]
+fn rolling_tower_follows_hero() {
+    // the hero is in sight but out of range, so the tower rolls closer before attacking
+    let mut simulation = Simulation::new(
+        "
+=====================
+=                  p=
+=====================
+
+[spawns]
+rolling-tower 1,1
+",
+    );
+    simulation.step(120);
+    let (_, (col, row)) = simulation.enemies()[0];
+    assert_eq!(row, 1);
+    assert!(col > 1, "rolled towards the hero to col {}", col);
+}
+
+#[test]
+fn game_over_and_level_complete_in_one_frame() {
+    let mut simulation = Simulation::new(GUN_TOWER_ABOVE_HERO);
+    simulation.step(2);
+    let hero = simulation.hero();
+    let world = &mut simulation.app.world;
+    world.get_mut::<Lives>(hero).expect("lives").remaining = 0;
+    world
+        .insert_one(hero, Wreck { seconds_left: 0.0 })
+        .expect("hero exists");
+    for (faction, mut life) in world.query_mut::<(&Faction, &mut LifeCycle)>() {
+        if *faction == Faction::Enemy {
+            life.terminate();
+        }
+    }
+
+    simulation.step(2);
+    let state = simulation.state();
+    assert!(
+        state == AppState::GameOver || state == AppState::LevelComplete,
+        "state {:?}",
+        state
+    );
+}
+
+#[test]
+fn reloading_terrain_without_player_keeps_level() {
+    let mut simulation = Simulation::new(GUN_TOWER_ABOVE_HERO);
+    simulation.step(2);
+    let path = std::env::temp_dir().join("batufo-simulation-no-player.txt");
+    std::fs::write(&path, "\n=====\n=   =\n=====\n").expect("should write level");
+
+    simulation.load_level(path.to_str().expect("valid path"));
+    simulation.step(2);
+    std::fs::remove_file(&path).expect("should remove level");
+
+    assert_eq!(simulation.state(), AppState::Playing);
+    assert_eq!(simulation.hero_tile(), (2, 1));
+    assert_eq!(simulation.enemies(), vec![(100, (2, 4))]);
+}
+
+#[test]
+fn replays_recorded_input() {
+    let path = std::env::temp_dir().join("batufo-simulation-recording.txt");
+    let mut recorded = Simulation::with_game(
+        GUN_TOWER_ABOVE_HERO,
+        GamePlugin {
+            seed: Some(7),
+            record: Some(path.clone()),
+            ..Default::default()
+        },
+    );
+    recorded.step(2);
+    recorded.press(KeyCode::W);
+    recorded.press(KeyCode::Space);
+    recorded.step(20);
+    recorded.release(KeyCode::W);
+    recorded.step(40);
+    recorded.release(KeyCode::Space);
+    recorded.step(10);
+
+    let mut replayed = Simulation::with_game(
+        GUN_TOWER_ABOVE_HERO,
+        GamePlugin {
+            replay: Some(path.clone()),
+            ..Default::default()
+        },
+    );
+    replayed.step(72);
+    std::fs::remove_file(&path).expect("should remove recording");
+
+    assert_eq!(replayed.hero_tile(), recorded.hero_tile());
+    assert_eq!(replayed.hero_health(), recorded.hero_health());
+    assert_eq!(replayed.enemies(), recorded.enemies());
+}